@@ -0,0 +1,451 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// 玩家行动, Bet 为本轮下注额, Raise 为加注到的总额
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    Bet(u32),
+    Raise(u32),
+    AllIn,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Fold => write!(f, "fold"),
+            Action::Check => write!(f, "check"),
+            Action::Call => write!(f, "call"),
+            Action::Bet(amount) => write!(f, "bet {}", amount),
+            Action::Raise(to) => write!(f, "raise {}", to),
+            Action::AllIn => write!(f, "allin"),
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = ActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let command = parts.next().unwrap_or("").to_lowercase();
+        let amount = parts.next().map(|amount| {
+            amount
+                .parse::<u32>()
+                .map_err(|_| ActionError::Unrecognized(s.to_string()))
+        });
+        match (command.as_str(), amount) {
+            ("fold", None) => Ok(Action::Fold),
+            ("check", None) => Ok(Action::Check),
+            ("call", None) => Ok(Action::Call),
+            ("allin", None) => Ok(Action::AllIn),
+            ("bet", Some(amount)) => Ok(Action::Bet(amount?)),
+            ("raise", Some(amount)) => Ok(Action::Raise(amount?)),
+            _ => Err(ActionError::Unrecognized(s.to_string())),
+        }
+    }
+}
+
+/// 还没轮到自己时预先选好的行动，轮到时按当时的下注判断是否执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PreAction {
+    // 能过牌则过牌，否则弃牌
+    CheckFold,
+    // 只在能过牌时过牌，有人下注则作废
+    Check,
+    // 跟注到设置时的最高下注，下注额变了则作废
+    Call(u32),
+    // 无论下注多少都跟注
+    CallAny,
+    // 有人下注就弃牌，没人下注则作废
+    FoldToAnyBet,
+}
+
+impl PreAction {
+    /// 轮到玩家时换成实际行动，None 表示已经作废，由玩家自己决定
+    pub fn resolve(&self, legal: &LegalActions) -> Option<Action> {
+        let check_or_call = if legal.can_check() {
+            Action::Check
+        } else {
+            Action::Call
+        };
+        match *self {
+            PreAction::CheckFold if legal.can_check() => Some(Action::Check),
+            PreAction::CheckFold => Some(Action::Fold),
+            PreAction::Check => legal.can_check().then_some(Action::Check),
+            PreAction::Call(amount) => (legal.current_bet == amount).then_some(check_or_call),
+            PreAction::CallAny => Some(check_or_call),
+            PreAction::FoldToAnyBet => (!legal.can_check()).then_some(Action::Fold),
+        }
+    }
+}
+
+impl fmt::Display for PreAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreAction::CheckFold => write!(f, "check/fold"),
+            PreAction::Check => write!(f, "check"),
+            PreAction::Call(amount) => write!(f, "call {}", amount),
+            PreAction::CallAny => write!(f, "call any"),
+            PreAction::FoldToAnyBet => write!(f, "fold"),
+        }
+    }
+}
+
+impl FromStr for PreAction {
+    type Err = ActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<String> = s.split_whitespace().map(str::to_lowercase).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        match words.as_slice() {
+            ["check/fold"] => Ok(PreAction::CheckFold),
+            ["check"] => Ok(PreAction::Check),
+            ["call", "any"] => Ok(PreAction::CallAny),
+            ["call", amount] => amount
+                .parse::<u32>()
+                .map(PreAction::Call)
+                .map_err(|_| ActionError::Unrecognized(s.to_string())),
+            ["fold"] => Ok(PreAction::FoldToAnyBet),
+            _ => Err(ActionError::Unrecognized(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionError {
+    Unrecognized(String),
+    CheckFacingBet { to_call: u32 },
+    NothingToCall,
+    BetFacingBet { current_bet: u32 },
+    RaiseWithoutBet,
+    BelowMinimum { amount: u32, minimum: u32 },
+    AboveMaximum { amount: u32, maximum: u32 },
+    ExceedsStack { amount: u32, stack: u32 },
+    NoChips,
+    RaiseClosed,
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::Unrecognized(s) => write!(f, "unrecognized action: {:?}", s),
+            ActionError::CheckFacingBet { to_call } => {
+                write!(f, "cannot check while facing a bet, {} to call", to_call)
+            }
+            ActionError::NothingToCall => write!(f, "there is no bet to call"),
+            ActionError::BetFacingBet { current_bet } => {
+                write!(
+                    f,
+                    "cannot bet while facing a bet of {}, raise instead",
+                    current_bet
+                )
+            }
+            ActionError::RaiseWithoutBet => write!(f, "there is no bet to raise, bet instead"),
+            ActionError::BelowMinimum { amount, minimum } => {
+                write!(f, "{} is below the minimum of {}", amount, minimum)
+            }
+            ActionError::AboveMaximum { amount, maximum } => {
+                write!(f, "{} is above the maximum of {}", amount, maximum)
+            }
+            ActionError::ExceedsStack { amount, stack } => {
+                write!(f, "{} is more than the {} chips behind", amount, stack)
+            }
+            ActionError::NoChips => write!(f, "no chips left to bet"),
+            ActionError::RaiseClosed => {
+                write!(f, "betting was not reopened by a full raise, call or fold")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+/// 轮到玩家行动时的下注局面，判断行动是否合法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalActions {
+    // 玩家剩余筹码
+    pub stack: u32,
+    // 玩家本轮已下注
+    pub player_bet: u32,
+    // 本轮最高下注
+    pub current_bet: u32,
+    // 最小加注幅度
+    pub min_raise: u32,
+    // 面对不足额全下时已行动过的玩家不能再加注
+    pub raise_closed: bool,
+    // 下注结构允许下注或加注到的最大总额
+    pub raise_limit: u32,
+}
+
+impl LegalActions {
+    pub fn new(stack: u32, player_bet: u32, current_bet: u32, min_raise: u32) -> Self {
+        Self {
+            stack,
+            player_bet,
+            current_bet,
+            min_raise,
+            raise_closed: false,
+            raise_limit: u32::MAX,
+        }
+    }
+
+    pub fn to_call(&self) -> u32 {
+        self.current_bet.saturating_sub(self.player_bet)
+    }
+
+    /// 全下后本轮的下注总额
+    pub fn max_bet(&self) -> u32 {
+        self.player_bet + self.stack
+    }
+
+    /// 下注或加注到的最大总额
+    pub fn max_raise_to(&self) -> u32 {
+        self.max_bet().min(self.raise_limit)
+    }
+
+    /// 下注或加注到的最小总额
+    pub fn min_bet(&self) -> u32 {
+        self.current_bet + self.min_raise
+    }
+
+    pub fn can_check(&self) -> bool {
+        self.to_call() == 0
+    }
+
+    pub fn can_call(&self) -> bool {
+        self.to_call() > 0 && self.stack > self.to_call()
+    }
+
+    pub fn can_bet(&self) -> bool {
+        self.current_bet == 0
+            && self.max_bet() > self.min_bet()
+            && self.raise_limit >= self.min_bet()
+    }
+
+    pub fn can_raise(&self) -> bool {
+        !self.raise_closed
+            && self.current_bet > 0
+            && self.max_bet() > self.min_bet()
+            && self.raise_limit >= self.min_bet()
+    }
+
+    /// 全下是否在下注结构允许的范围内
+    pub fn can_allin(&self) -> bool {
+        self.stack > 0
+            && (self.allin_is_call() || (!self.raise_closed && self.max_bet() <= self.raise_limit))
+    }
+
+    /// 全下是否只是跟注（筹码不超过需要跟注的金额）
+    pub fn allin_is_call(&self) -> bool {
+        self.stack <= self.to_call()
+    }
+
+    /// 当前可选的行动, 下注和加注给出最小金额
+    pub fn actions(&self) -> Vec<Action> {
+        let mut actions = vec![Action::Fold]; // 总是可以选择弃牌
+        if self.can_check() {
+            actions.push(Action::Check);
+        }
+        if self.can_call() {
+            actions.push(Action::Call);
+        }
+        if self.can_bet() {
+            actions.push(Action::Bet(self.min_bet()));
+        }
+        if self.can_raise() {
+            actions.push(Action::Raise(self.min_bet()));
+        }
+        if self.can_allin() {
+            actions.push(Action::AllIn);
+        }
+        actions
+    }
+
+    /*
+     * 校验玩家行动
+     * 下注或加注的金额恰好为全部筹码时视为全下，跟注筹码不足时同样视为全下
+     */
+    pub fn validate(&self, action: Action) -> Result<Action, ActionError> {
+        match action {
+            Action::Fold => Ok(Action::Fold),
+            Action::Check if self.can_check() => Ok(Action::Check),
+            Action::Check => Err(ActionError::CheckFacingBet {
+                to_call: self.to_call(),
+            }),
+            Action::Call if self.to_call() == 0 => Err(ActionError::NothingToCall),
+            Action::Call if self.stack <= self.to_call() => Ok(Action::AllIn),
+            Action::Call => Ok(Action::Call),
+            Action::Bet(_) if self.current_bet > 0 => Err(ActionError::BetFacingBet {
+                current_bet: self.current_bet,
+            }),
+            Action::Raise(_) if self.current_bet == 0 => Err(ActionError::RaiseWithoutBet),
+            Action::Raise(_) if self.raise_closed => Err(ActionError::RaiseClosed),
+            Action::Bet(amount) | Action::Raise(amount) => {
+                if amount > self.max_bet() {
+                    Err(ActionError::ExceedsStack {
+                        amount,
+                        stack: self.stack,
+                    })
+                } else if amount > self.raise_limit {
+                    Err(ActionError::AboveMaximum {
+                        amount,
+                        maximum: self.raise_limit,
+                    })
+                } else if amount == self.max_bet() {
+                    Ok(Action::AllIn)
+                } else if amount < self.min_bet() {
+                    Err(ActionError::BelowMinimum {
+                        amount,
+                        minimum: self.min_bet(),
+                    })
+                } else {
+                    Ok(action)
+                }
+            }
+            Action::AllIn if self.stack == 0 => Err(ActionError::NoChips),
+            Action::AllIn if self.raise_closed && !self.allin_is_call() => {
+                Err(ActionError::RaiseClosed)
+            }
+            Action::AllIn if !self.can_allin() => Err(ActionError::AboveMaximum {
+                amount: self.max_bet(),
+                maximum: self.raise_limit,
+            }),
+            Action::AllIn => Ok(Action::AllIn),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!("fold".parse(), Ok(Action::Fold));
+        assert_eq!("Raise 60".parse(), Ok(Action::Raise(60)));
+        assert_eq!("bet 40".parse(), Ok(Action::Bet(40)));
+        assert!("raise".parse::<Action>().is_err());
+        assert!("bet many".parse::<Action>().is_err());
+        assert_eq!(Action::Raise(60).to_string(), "raise 60");
+    }
+
+    #[test]
+    fn test_actions() {
+        let unopened = LegalActions::new(1000, 0, 0, 20);
+        assert_eq!(
+            unopened.actions(),
+            vec![Action::Fold, Action::Check, Action::Bet(20), Action::AllIn]
+        );
+        let facing_bet = LegalActions::new(1000, 10, 20, 20);
+        assert_eq!(
+            facing_bet.actions(),
+            vec![Action::Fold, Action::Call, Action::Raise(40), Action::AllIn]
+        );
+        let short = LegalActions::new(15, 0, 20, 20);
+        assert_eq!(short.actions(), vec![Action::Fold, Action::AllIn]);
+        let mut closed = LegalActions::new(1000, 100, 150, 80);
+        closed.raise_closed = true;
+        assert_eq!(closed.actions(), vec![Action::Fold, Action::Call]);
+        assert_eq!(
+            closed.validate(Action::Raise(300)),
+            Err(ActionError::RaiseClosed)
+        );
+        assert_eq!(
+            closed.validate(Action::AllIn),
+            Err(ActionError::RaiseClosed)
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let facing_bet = LegalActions::new(100, 0, 20, 20);
+        assert_eq!(
+            facing_bet.validate(Action::Check),
+            Err(ActionError::CheckFacingBet { to_call: 20 })
+        );
+        assert_eq!(
+            facing_bet.validate(Action::Raise(30)),
+            Err(ActionError::BelowMinimum {
+                amount: 30,
+                minimum: 40
+            })
+        );
+        assert_eq!(
+            facing_bet.validate(Action::Raise(200)),
+            Err(ActionError::ExceedsStack {
+                amount: 200,
+                stack: 100
+            })
+        );
+        assert_eq!(
+            facing_bet.validate(Action::Bet(40)),
+            Err(ActionError::BetFacingBet { current_bet: 20 })
+        );
+        assert_eq!(
+            facing_bet.validate(Action::Raise(40)),
+            Ok(Action::Raise(40))
+        );
+        assert_eq!(facing_bet.validate(Action::Raise(100)), Ok(Action::AllIn));
+
+        let unopened = LegalActions::new(100, 0, 0, 20);
+        assert_eq!(
+            unopened.validate(Action::Call),
+            Err(ActionError::NothingToCall)
+        );
+        assert_eq!(
+            unopened.validate(Action::Raise(40)),
+            Err(ActionError::RaiseWithoutBet)
+        );
+        assert_eq!(
+            unopened.validate(Action::Bet(10)),
+            Err(ActionError::BelowMinimum {
+                amount: 10,
+                minimum: 20
+            })
+        );
+        assert_eq!(unopened.validate(Action::Bet(20)), Ok(Action::Bet(20)));
+
+        let short = LegalActions::new(15, 0, 20, 20);
+        assert_eq!(short.validate(Action::Call), Ok(Action::AllIn));
+        assert_eq!(
+            LegalActions::new(0, 20, 20, 20).validate(Action::AllIn),
+            Err(ActionError::NoChips)
+        );
+    }
+
+    #[test]
+    fn test_pre_action() {
+        let unopened = LegalActions::new(1000, 20, 20, 20);
+        let facing_bet = LegalActions::new(1000, 0, 60, 40);
+        assert_eq!(PreAction::CheckFold.resolve(&unopened), Some(Action::Check));
+        assert_eq!(
+            PreAction::CheckFold.resolve(&facing_bet),
+            Some(Action::Fold)
+        );
+        assert_eq!(PreAction::Check.resolve(&facing_bet), None);
+        assert_eq!(PreAction::Call(60).resolve(&facing_bet), Some(Action::Call));
+        // 有人加注后跟注作废
+        assert_eq!(PreAction::Call(20).resolve(&facing_bet), None);
+        assert_eq!(PreAction::CallAny.resolve(&facing_bet), Some(Action::Call));
+        assert_eq!(PreAction::CallAny.resolve(&unopened), Some(Action::Check));
+        assert_eq!(PreAction::FoldToAnyBet.resolve(&unopened), None);
+        assert_eq!(
+            PreAction::FoldToAnyBet.resolve(&facing_bet),
+            Some(Action::Fold)
+        );
+    }
+
+    #[test]
+    fn test_parse_pre_action() {
+        assert_eq!("check/fold".parse(), Ok(PreAction::CheckFold));
+        assert_eq!("Call Any".parse(), Ok(PreAction::CallAny));
+        assert_eq!("call 60".parse(), Ok(PreAction::Call(60)));
+        assert_eq!("fold".parse(), Ok(PreAction::FoldToAnyBet));
+        assert!("call".parse::<PreAction>().is_err());
+        assert_eq!(PreAction::Call(60).to_string(), "call 60");
+    }
+}
@@ -0,0 +1,127 @@
+use crate::action::LegalActions;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 下注结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum BettingStructure {
+    // 无限注: 最小加注为上一次加注的幅度，最多全下
+    #[default]
+    NoLimit,
+    // 底池限注: 最多加注到跟注后的底池大小
+    PotLimit,
+    // 固定限注: 翻牌前和翻牌用小注，转牌和河牌用大注，每条街最多 raise_cap 次下注/加注
+    FixedLimit {
+        small_bet: u32,
+        big_bet: u32,
+        raise_cap: u32,
+    },
+}
+
+/// 前注，前注是死筹码，不计入本轮下注
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Ante {
+    #[default]
+    None,
+    // 每位玩家各下一份前注
+    PerPlayer(u32),
+    // 大盲替全桌下一份前注，常见于锦标赛
+    BigBlind(u32),
+}
+
+impl fmt::Display for Ante {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ante::None => write!(f, "0"),
+            Ante::PerPlayer(ante) => write!(f, "{}", ante),
+            Ante::BigBlind(ante) => write!(f, "{} (big blind)", ante),
+        }
+    }
+}
+
+impl BettingStructure {
+    /*
+     * 每条街开始时的最小下注/加注幅度
+     * @param late_street: bool 是否为转牌或河牌
+     */
+    pub fn bet_size(&self, big_blind: u32, late_street: bool) -> u32 {
+        match *self {
+            BettingStructure::FixedLimit {
+                small_bet, big_bet, ..
+            } => {
+                if late_street {
+                    big_bet
+                } else {
+                    small_bet
+                }
+            }
+            _ => big_blind,
+        }
+    }
+
+    /*
+     * 下注或加注到的最大总额
+     * @param pot: u32 底池加上本轮所有玩家的下注
+     * @param raises: u32 本轮已有的下注/加注次数
+     */
+    pub fn raise_limit(&self, legal: &LegalActions, pot: u32, raises: u32) -> u32 {
+        match *self {
+            BettingStructure::NoLimit => u32::MAX,
+            BettingStructure::PotLimit => legal.current_bet + pot + legal.to_call(),
+            BettingStructure::FixedLimit { raise_cap, .. } => {
+                if raises >= raise_cap {
+                    legal.current_bet
+                } else {
+                    legal.current_bet + legal.min_raise
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+
+    const FIXED_LIMIT: BettingStructure = BettingStructure::FixedLimit {
+        small_bet: 20,
+        big_bet: 40,
+        raise_cap: 4,
+    };
+
+    #[test]
+    fn test_bet_size() {
+        assert_eq!(BettingStructure::NoLimit.bet_size(20, true), 20);
+        assert_eq!(FIXED_LIMIT.bet_size(20, false), 20);
+        assert_eq!(FIXED_LIMIT.bet_size(20, true), 40);
+    }
+
+    #[test]
+    fn test_pot_limit() {
+        // 盲注 10/20，UTG 跟注 20 后底池 50，最多加注到 70
+        let mut legal = LegalActions::new(1000, 0, 20, 20);
+        legal.raise_limit = BettingStructure::PotLimit.raise_limit(&legal, 30, 1);
+        assert_eq!(legal.raise_limit, 70);
+        assert_eq!(legal.validate(Action::Raise(70)), Ok(Action::Raise(70)));
+        assert!(legal.validate(Action::Raise(80)).is_err());
+        assert!(legal.validate(Action::AllIn).is_err());
+        assert_eq!(
+            legal.actions(),
+            vec![Action::Fold, Action::Call, Action::Raise(40)]
+        );
+    }
+
+    #[test]
+    fn test_fixed_limit() {
+        let mut legal = LegalActions::new(1000, 0, 40, 40);
+        legal.raise_limit = FIXED_LIMIT.raise_limit(&legal, 100, 1);
+        assert_eq!(legal.raise_limit, 80);
+        assert_eq!(legal.validate(Action::Raise(80)), Ok(Action::Raise(80)));
+        assert!(legal.validate(Action::Raise(100)).is_err());
+
+        legal.raise_limit = FIXED_LIMIT.raise_limit(&legal, 100, 4);
+        assert!(!legal.can_raise());
+        assert_eq!(legal.actions(), vec![Action::Fold, Action::Call]);
+    }
+}
@@ -1,7 +1,12 @@
+use crate::card_set::CardSet;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
+use rand::{RngCore, SeedableRng};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Suit {
     Clubs,
     Diamonds,
@@ -21,27 +26,164 @@ impl fmt::Display for Suit {
     }
 }
 
+impl Suit {
+    /// 花色的字母写法
+    pub fn letter(self) -> char {
+        match self {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+        }
+    }
+
+    fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0 => Some(Suit::Clubs),
+            1 => Some(Suit::Diamonds),
+            2 => Some(Suit::Hearts),
+            3 => Some(Suit::Spades),
+            _ => None,
+        }
+    }
+}
+
+/*
+ * 序列化: 文本格式 (JSON 等) 写成字母 "h"，二进制格式 (bincode) 写成一个字节
+ */
+impl Serialize for Suit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_char(self.letter())
+        } else {
+            serializer.serialize_u8(*self as u8)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Suit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+                .parse()
+                .map_err(de::Error::custom)
+        } else {
+            let index = u8::deserialize(deserializer)?;
+            Suit::from_index(index)
+                .ok_or_else(|| de::Error::custom(format!("invalid suit {}", index)))
+        }
+    }
+}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    /// 花色可以写成符号或 c/d/h/s 字母，不区分大小写
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "♣" | "c" => Ok(Suit::Clubs),
+            "♦" | "d" => Ok(Suit::Diamonds),
+            "♥" | "h" => Ok(Suit::Hearts),
+            "♠" | "s" => Ok(Suit::Spades),
+            _ => Err(ParseCardError::InvalidSuit(s.to_string())),
+        }
+    }
+}
+
+/// 字面量写牌时使用，格式错误会 panic，解析用户或网络输入请用 `str::parse`
 impl From<&str> for Suit {
     fn from(s: &str) -> Self {
-        match s {
-            "♣" => Suit::Clubs,
-            "♦" => Suit::Diamonds,
-            "♥" => Suit::Hearts,
-            "♠" => Suit::Spades,
-            &_ => panic!("Invalid suit"),
+        s.parse().unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCardError {
+    Empty,
+    InvalidRank(String),
+    InvalidSuit(String),
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseCardError::Empty => write!(f, "missing card"),
+            ParseCardError::InvalidRank(rank) => write!(f, "invalid rank '{}'", rank),
+            ParseCardError::InvalidSuit(suit) => write!(f, "invalid suit '{}'", suit),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl std::error::Error for ParseCardError {}
+
+/// 先按点数再按花色排序，和按花色排列的 `Card::index` 不同
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Card {
     pub rank: u8,
     pub suit: Suit,
 }
 
 impl Card {
+    /// 点数为 2..=14 (A 是 14)，超出范围会 panic，不确定时用 `Card::try_new`
     pub fn new(rank: u8, suit: Suit) -> Self {
-        Self { rank, suit }
+        Self::try_new(rank, suit).unwrap_or_else(|| panic!("invalid rank {}", rank))
+    }
+
+    pub fn try_new(rank: u8, suit: Suit) -> Option<Self> {
+        (2..=14).contains(&rank).then_some(Self { rank, suit })
+    }
+
+    /*
+     * 0..52 的编号，按花色再按点数排列，梅花 2 是 0，梅花 A 是 12，黑桃 A 是 51
+     * 二进制序列化和 `Deck` 洗牌前的牌序都用这个编号，改动会让同一个种子发出不同的牌
+     */
+    pub fn index(&self) -> u8 {
+        self.suit as u8 * 13 + self.rank - 2
+    }
+
+    pub fn from_index(index: u8) -> Option<Self> {
+        match index {
+            0..=51 => Suit::from_index(index / 13).map(|suit| Card::new(index % 13 + 2, suit)),
+            _ => None,
+        }
+    }
+
+    /// ASCII 写法，例如 "Ah"、"Td"，可以用 `str::parse` 解析回来
+    pub fn notation(&self) -> String {
+        let rank = match self.rank {
+            10 => 'T',
+            11 => 'J',
+            12 => 'Q',
+            13 => 'K',
+            14 => 'A',
+            rank => (b'0' + rank) as char,
+        };
+        format!("{}{}", rank, self.suit.letter())
+    }
+}
+
+/// 序列化: 文本格式写成 "Ah"，二进制格式写成 `Card::index` 一个字节
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.notation())
+        } else {
+            serializer.serialize_u8(self.index())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+                .parse()
+                .map_err(de::Error::custom)
+        } else {
+            let byte = u8::deserialize(deserializer)?;
+            Card::from_index(byte)
+                .ok_or_else(|| de::Error::custom(format!("invalid card {}", byte)))
+        }
     }
 }
 
@@ -58,60 +200,187 @@ impl fmt::Display for Card {
     }
 }
 
-impl From<&str> for Card {
-    fn from(s: &str) -> Self {
-        let parts = s.chars();
-        if parts.count() != 2 {
-            panic!("Invalid card");
-        }
-        let suit = s.chars().nth(0).unwrap().to_string();
-        let rank = s.chars().nth(1).unwrap().to_string();
+impl FromStr for Card {
+    type Err = ParseCardError;
 
-        let suit = Suit::from(suit.as_str());
-        let rank = match rank.as_str() {
+    /*
+     * 支持点数在前的 "Ah"、"Td"、"10s"，以及花色符号在前的 "♥X"
+     * 10 可以写成 T、X 或 10，字母不区分大小写
+     */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let (rank, suit) = match (chars.next(), chars.next_back()) {
+            (None, _) => return Err(ParseCardError::Empty),
+            (Some(first), _) if "♣♦♥♠".contains(first) => (&s[first.len_utf8()..], first),
+            (_, Some(last)) => (&s[..s.len() - last.len_utf8()], last),
+            (Some(_), None) => return Err(ParseCardError::InvalidRank(s.to_string())),
+        };
+        let suit = suit.to_string().parse()?;
+        let rank = match rank.to_ascii_uppercase().as_str() {
             "A" => 14,
             "K" => 13,
             "Q" => 12,
             "J" => 11,
-            "X" => 10,
-            num => num.parse().unwrap(),
+            "T" | "X" | "10" => 10,
+            num => match num.parse() {
+                Ok(rank @ 2..=9) => rank,
+                _ => return Err(ParseCardError::InvalidRank(rank.to_string())),
+            },
         };
-        Card { rank, suit }
+        Ok(Card { rank, suit })
+    }
+}
+
+/// 字面量写牌时使用，格式错误会 panic，解析用户或网络输入请用 `str::parse`
+impl From<&str> for Card {
+    fn from(s: &str) -> Self {
+        s.parse().unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
-pub fn parse_cards(input: &str) -> Vec<Card> {
-    input.split(';').map(|s| Card::from(s)).collect()
+/// 解析用分号、逗号或空格分隔的一组牌
+pub fn parse_cards(input: &str) -> Result<Vec<Card>, ParseCardError> {
+    input
+        .split(|c: char| c == ';' || c == ',' || c.is_whitespace())
+        .filter(|card| !card.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeckError {
+    // 指定的牌不在一副牌里
+    InvalidCard(Card),
+    DuplicateCard(Card),
+    TooManyCards(usize),
+}
+
+impl fmt::Display for DeckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeckError::InvalidCard(card) => write!(f, "{} is not a valid card", card),
+            DeckError::DuplicateCard(card) => write!(f, "{} is used more than once", card),
+            DeckError::TooManyCards(count) => write!(f, "{} cards do not fit in a deck", count),
+        }
+    }
 }
 
+impl std::error::Error for DeckError {}
+
 pub struct Deck {
     all_cards: Vec<Card>,
     pub cards: Vec<Card>,
+    // 预设的发牌顺序，None 的位置随机补牌，每次洗牌后都按它排好牌堆顶
+    preset: Vec<Option<Card>>,
+    // 每次洗牌先从这里取一个种子
+    rng: StdRng,
+    // 上一次洗牌用的种子，用它可以还原那一次的牌序
+    pub seed: Option<u64>,
 }
 
 impl Deck {
     pub fn new() -> Self {
-        let mut cards = Vec::with_capacity(52);
-        for suit in &[Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades] {
-            for rank in 2..=14 {
-                cards.push(Card::new(rank, *suit));
+        Self::with_rng(StdRng::from_entropy())
+    }
+
+    /// 同一个种子洗出的每一手牌都相同
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
+        let cards: Vec<Card> = CardSet::full().iter().collect();
+        let all_cards = cards.clone();
+        Self {
+            all_cards,
+            cards,
+            rng,
+            seed: None,
+            preset: Vec::new(),
+        }
+    }
+
+    /// 按给定顺序发牌，先给出的先发，没给出的牌随机排在后面
+    pub fn from_cards(order: &[Card]) -> Result<Self, DeckError> {
+        Self::with_preset(order.iter().copied().map(Some).collect())
+    }
+
+    /*
+     * 预设手牌和公共牌，其余的牌随机
+     * hands 按玩家下标顺序，每位拿到手牌的玩家一项，None 表示这位玩家的手牌随机
+     * board 依次是翻牌、转牌、河牌，可以只给出前几张
+     */
+    pub fn with_hands(hands: &[Option<(Card, Card)>], board: &[Card]) -> Result<Self, DeckError> {
+        let preset = hands
+            .iter()
+            .flat_map(|hand| match hand {
+                Some((first, second)) => [Some(*first), Some(*second)],
+                None => [None, None],
+            })
+            .chain(board.iter().copied().map(Some))
+            .collect();
+        Self::with_preset(preset)
+    }
+
+    fn with_preset(preset: Vec<Option<Card>>) -> Result<Self, DeckError> {
+        let mut deck = Self::new();
+        if preset.len() > deck.all_cards.len() {
+            return Err(DeckError::TooManyCards(preset.len()));
+        }
+        let mut pinned = CardSet::new();
+        for &card in preset.iter().flatten() {
+            if !deck.all_cards.contains(&card) {
+                return Err(DeckError::InvalidCard(card));
+            }
+            if !pinned.insert(card) {
+                return Err(DeckError::DuplicateCard(card));
             }
         }
-        let all_cards = cards.clone();
-        Self { all_cards, cards }
+        deck.preset = preset;
+        deck.shuffle();
+        Ok(deck)
     }
 
+    /// 取一个新种子洗牌，种子记在 `seed` 里
     pub fn shuffle(&mut self) {
-        if self.cards.len() < 52 {
-            self.cards = self.all_cards.clone();
+        let seed = self.rng.next_u64();
+        self.shuffle_with(&mut StdRng::seed_from_u64(seed));
+        self.seed = Some(seed);
+    }
+
+    /// 收回所有牌后用给定的随机数生成器洗牌
+    pub fn shuffle_with<R: RngCore + ?Sized>(&mut self, rng: &mut R) {
+        self.cards = self.all_cards.clone();
+        self.cards.as_mut_slice().shuffle(rng);
+        if !self.preset.is_empty() {
+            let pinned: CardSet = self.preset.iter().flatten().copied().collect();
+            let mut rest = self
+                .cards
+                .iter()
+                .filter(|&&card| !pinned.contains(card))
+                .copied();
+            let mut order: Vec<Card> = self
+                .preset
+                .iter()
+                .map(|slot| slot.unwrap_or_else(|| rest.next().unwrap()))
+                .collect();
+            order.extend(rest);
+            // 从末尾发牌，倒过来放
+            order.reverse();
+            self.cards = order;
         }
-        let mut rng = rand::thread_rng();
-        self.cards.as_mut_slice().shuffle(&mut rng);
+        self.seed = None;
     }
 
     pub fn deal(&mut self) -> Card {
         self.cards.pop().unwrap()
     }
+
+    /// 牌堆里还没发出的牌
+    pub fn remaining(&self) -> CardSet {
+        self.cards.iter().copied().collect()
+    }
 }
 
 impl Default for Deck {
@@ -133,14 +402,182 @@ mod tests {
     fn test_card_creation() {
         let card = Card::new(10, Suit::Hearts);
         assert_eq!(card, Card::from("♥X"));
+        assert_eq!(Card::try_new(14, Suit::Spades), Some(Card::from("As")));
+        assert_eq!(Card::try_new(1, Suit::Spades), None);
+        assert_eq!(Card::try_new(15, Suit::Spades), None);
+    }
+
+    #[test]
+    fn test_parse_card() {
+        let ace = Card::new(14, Suit::Hearts);
+        assert_eq!("Ah".parse(), Ok(ace));
+        assert_eq!("♥A".parse(), Ok(ace));
+        assert_eq!(" aH ".parse(), Ok(ace));
+        assert_eq!("Td".parse(), Ok(Card::new(10, Suit::Diamonds)));
+        assert_eq!("10s".parse(), Ok(Card::new(10, Suit::Spades)));
+        assert_eq!("♣X".parse(), Ok(Card::new(10, Suit::Clubs)));
+        assert_eq!("2c".parse(), Ok(Card::new(2, Suit::Clubs)));
+
+        assert_eq!("".parse::<Card>(), Err(ParseCardError::Empty));
+        assert_eq!(
+            "A".parse::<Card>(),
+            Err(ParseCardError::InvalidRank("A".to_string()))
+        );
+        assert_eq!(
+            "1h".parse::<Card>(),
+            Err(ParseCardError::InvalidRank("1".to_string()))
+        );
+        assert_eq!(
+            "Ax".parse::<Card>(),
+            Err(ParseCardError::InvalidSuit("x".to_string()))
+        );
+        assert_eq!(
+            "♥".parse::<Card>(),
+            Err(ParseCardError::InvalidRank(String::new()))
+        );
+        assert!("11h".parse::<Card>().is_err());
+        assert!("Ahh".parse::<Card>().is_err());
+    }
+
+    #[test]
+    fn test_parse_cards() {
+        let cards = vec![Card::new(14, Suit::Spades), Card::new(13, Suit::Hearts)];
+        assert_eq!(parse_cards("♠A;♥K"), Ok(cards.clone()));
+        assert_eq!(parse_cards("As Kh"), Ok(cards.clone()));
+        assert_eq!(parse_cards("As, Kh"), Ok(cards));
+        assert_eq!(parse_cards(""), Ok(Vec::new()));
+        assert!(parse_cards("As Kx").is_err());
+    }
+
+    #[test]
+    fn test_card_serde() {
+        let cards = parse_cards("Ah Td 2c Ks").unwrap();
+        let bytes = bincode::serialize(&cards).unwrap();
+        // 8 字节的长度加上每张牌一个字节
+        assert_eq!(bytes.len(), 8 + 4);
+        assert_eq!(bytes[8..], [38, 21, 0, 50]);
+        assert_eq!(bincode::deserialize::<Vec<Card>>(&bytes).unwrap(), cards);
+        assert!(bincode::deserialize::<Card>(&[52]).is_err());
+
+        let text = serde_json::to_string(&cards).unwrap();
+        assert_eq!(text, r#"["Ah","Td","2c","Ks"]"#);
+        assert_eq!(serde_json::from_str::<Vec<Card>>(&text).unwrap(), cards);
+        assert_eq!(serde_json::to_string(&Suit::Hearts).unwrap(), r#""h""#);
+        assert!(serde_json::from_str::<Card>(r#""Zz""#).is_err());
+
+        let deck = Deck::new();
+        for card in &deck.all_cards {
+            let bytes = bincode::serialize(card).unwrap();
+            assert_eq!(bincode::deserialize::<Card>(&bytes).unwrap(), *card);
+        }
     }
 
     #[test]
     fn test_shuffle() {
-        let mut deck = Deck::new();
+        let mut deck = Deck::with_seed(7);
         let original_deck = deck.cards.clone();
         deck.shuffle();
         assert_ne!(deck.cards, original_deck);
+
+        let mut same = Deck::with_seed(7);
+        same.shuffle();
+        assert_eq!(deck.cards, same.cards);
+        assert_eq!(deck.seed, same.seed);
+        deck.shuffle();
+        assert_ne!(deck.cards, same.cards);
+    }
+
+    #[test]
+    fn test_seed_golden_deal() {
+        // 洗牌前按花色再按点数排列，和 `Card::index` 一致
+        let deck = Deck::with_seed(42);
+        assert_eq!(deck.cards[..2], parse_cards("2c 3c").unwrap()[..]);
+        assert_eq!(deck.cards[13], Card::from("2d"));
+        // 固定种子发出的牌不能变，否则记录下来的种子无法还原牌局
+        let mut deck = Deck::with_seed(42);
+        deck.shuffle();
+        let dealt: Vec<Card> = (0..9).map(|_| deck.deal()).collect();
+        assert_eq!(deck.seed, Some(9713269763989775522));
+        assert_eq!(dealt, parse_cards("6s 9h 4s 9d Tc 8h 6h As 4c").unwrap());
+    }
+
+    #[test]
+    fn test_replay_shuffle_from_seed() {
+        let mut deck = Deck::new();
+        deck.deal();
+        deck.shuffle();
+        assert_eq!(deck.cards.len(), 52);
+        let mut replay = Deck::new();
+        replay.shuffle_with(&mut StdRng::seed_from_u64(deck.seed.unwrap()));
+        assert_eq!(deck.cards, replay.cards);
+    }
+
+    #[test]
+    fn test_stacked_deck() {
+        let order = parse_cards("♠A;♥K;♦Q").unwrap();
+        let mut deck = Deck::from_cards(&order).unwrap();
+        assert_eq!(deck.cards.len(), 52);
+        assert_eq!(
+            (deck.deal(), deck.deal(), deck.deal()),
+            (order[0], order[1], order[2])
+        );
+        // 重新洗牌后仍然按预设发牌
+        deck.shuffle();
+        assert_eq!(deck.deal(), order[0]);
+
+        let board = parse_cards("♣9;♥8;♦2").unwrap();
+        let mut deck =
+            Deck::with_hands(&[None, Some((Card::from("♠9"), Card::from("♥9")))], &board).unwrap();
+        let dealt: Vec<Card> = (0..7).map(|_| deck.deal()).collect();
+        assert_eq!(dealt[2..4], parse_cards("♠9;♥9").unwrap()[..]);
+        assert_eq!(dealt[4..], board[..]);
+        assert!(!dealt[..2].iter().any(|card| dealt[2..].contains(card)));
+        assert_ne!(dealt[0], dealt[1]);
+    }
+
+    #[test]
+    fn test_stacked_deck_validation() {
+        assert_eq!(
+            Deck::from_cards(&parse_cards("♠A;♥K;♠A").unwrap()).err(),
+            Some(DeckError::DuplicateCard(Card::from("♠A")))
+        );
+        assert_eq!(
+            Deck::with_hands(
+                &[Some((Card::from("♠A"), Card::from("♥K")))],
+                &parse_cards("♥K").unwrap()
+            )
+            .err(),
+            Some(DeckError::DuplicateCard(Card::from("♥K")))
+        );
+        assert_eq!(
+            Deck::from_cards(&[Card {
+                rank: 1,
+                suit: Suit::Hearts
+            }])
+            .err(),
+            Some(DeckError::InvalidCard(Card {
+                rank: 1,
+                suit: Suit::Hearts
+            }))
+        );
+        assert_eq!(
+            Deck::with_hands(&[None; 27], &[]).err(),
+            Some(DeckError::TooManyCards(54))
+        );
+    }
+
+    #[test]
+    fn test_card_index() {
+        assert_eq!(Card::from("2c").index(), 0);
+        assert_eq!(Card::from("Ac").index(), 12);
+        assert_eq!(Card::from("2d").index(), 13);
+        assert_eq!(Card::from("As").index(), 51);
+        for index in 0..52 {
+            assert_eq!(Card::from_index(index).unwrap().index(), index);
+        }
+        assert_eq!(Card::from_index(52), None);
+        assert!(Card::from("Kh") < Card::from("Ac"));
+        assert!(Card::from("Ac") < Card::from("Ad"));
     }
 
     #[test]
@@ -148,5 +585,6 @@ mod tests {
         let mut deck = Deck::new();
         deck.deal();
         assert_eq!(deck.cards.len(), 51);
+        assert_eq!(deck.remaining().len(), 51);
     }
 }
@@ -0,0 +1,260 @@
+use crate::card::Card;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Sub};
+
+/*
+ * 用 64 位掩码表示的一组牌，第 `Card::index` 位表示这张牌在不在集合里
+ * 插入、删除、查找和求并集都不分配内存，迭代时按 `Card::index` 从小到大，即按花色再按点数
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    const FULL: u64 = (1 << 52) - 1;
+
+    pub fn new() -> Self {
+        Self(0)
+    }
+
+    /// 一整副 52 张牌
+    pub fn full() -> Self {
+        Self(Self::FULL)
+    }
+
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits & Self::FULL)
+    }
+
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(self, card: Card) -> bool {
+        self.0 & Self::bit(card) != 0
+    }
+
+    /// @return bool 这张牌原来不在集合里
+    pub fn insert(&mut self, card: Card) -> bool {
+        let absent = !self.contains(card);
+        self.0 |= Self::bit(card);
+        absent
+    }
+
+    /// @return bool 这张牌原来在集合里
+    pub fn remove(&mut self, card: Card) -> bool {
+        let present = self.contains(card);
+        self.0 &= !Self::bit(card);
+        present
+    }
+
+    pub fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    pub fn difference(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    /// 整副牌里不在这个集合中的牌
+    pub fn complement(self) -> Self {
+        Self(!self.0 & Self::FULL)
+    }
+
+    pub fn iter(self) -> Iter {
+        Iter(self.0)
+    }
+
+    fn bit(card: Card) -> u64 {
+        1 << card.index()
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        self.union(other)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        self.intersection(other)
+    }
+}
+
+impl Sub for CardSet {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.difference(other)
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, iter: I) {
+        for card in iter {
+            self.insert(card);
+        }
+    }
+}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        self.iter()
+    }
+}
+
+impl fmt::Display for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cards = self
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(f, "[{}]", cards)
+    }
+}
+
+/// 序列化: 文本格式写成牌的列表 ["Ah","Td"]，二进制格式写成掩码 u64
+impl Serialize for CardSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.iter())
+        } else {
+            serializer.serialize_u64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CardSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            Ok(Vec::<Card>::deserialize(deserializer)?
+                .into_iter()
+                .collect())
+        } else {
+            let bits = u64::deserialize(deserializer)?;
+            if bits & !Self::FULL != 0 {
+                return Err(de::Error::custom(format!("invalid card set {:#x}", bits)));
+            }
+            Ok(Self(bits))
+        }
+    }
+}
+
+/// 从最低位开始逐个取出集合里的牌
+pub struct Iter(u64);
+
+impl Iterator for Iter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros() as u8;
+        self.0 &= self.0 - 1;
+        Card::from_index(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Iter {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::parse_cards;
+
+    #[test]
+    fn test_insert_remove() {
+        let ace = Card::from("As");
+        let mut set = CardSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(ace));
+        assert!(!set.insert(ace));
+        assert!(set.contains(ace));
+        assert_eq!(set.len(), 1);
+        assert!(set.remove(ace));
+        assert!(!set.remove(ace));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let hand: CardSet = parse_cards("Ah Kh").unwrap().into_iter().collect();
+        let board: CardSet = parse_cards("Kh Qh Jh").unwrap().into_iter().collect();
+        assert_eq!((hand | board).len(), 4);
+        assert_eq!(hand & board, CardSet::from_iter([Card::from("Kh")]));
+        assert_eq!(
+            (hand - board).iter().collect::<Vec<Card>>(),
+            vec![Card::from("Ah")]
+        );
+        assert_eq!(hand.complement().len(), 50);
+        assert_eq!(CardSet::full().len(), 52);
+        assert_eq!(CardSet::full().complement(), CardSet::new());
+        assert_eq!(CardSet::from_bits(u64::MAX), CardSet::full());
+    }
+
+    #[test]
+    fn test_iter_in_order() {
+        let set: CardSet = parse_cards("As 2c Td 2d").unwrap().into_iter().collect();
+        assert_eq!(set.iter().len(), 4);
+        assert_eq!(
+            set.iter().collect::<Vec<Card>>(),
+            parse_cards("2c 2d Td As").unwrap()
+        );
+        assert_eq!(set.to_string(), "[2♣, 2♦, 10♦, A♠]");
+        let all: Vec<Card> = CardSet::full().into_iter().collect();
+        assert_eq!(all.len(), 52);
+        assert!(all.windows(2).all(|pair| pair[0].index() < pair[1].index()));
+        // 同一花色的牌排在一起
+        assert_eq!(all[12], Card::from("Ac"));
+        assert_eq!(all[13], Card::from("2d"));
+    }
+
+    #[test]
+    fn test_serde() {
+        let set: CardSet = parse_cards("Ah Td 2c").unwrap().into_iter().collect();
+        let bytes = bincode::serialize(&set).unwrap();
+        assert_eq!(bytes.len(), 8);
+        assert_eq!(bincode::deserialize::<CardSet>(&bytes).unwrap(), set);
+        assert!(bincode::deserialize::<CardSet>(&u64::MAX.to_le_bytes()).is_err());
+
+        let text = serde_json::to_string(&set).unwrap();
+        assert_eq!(text, r#"["2c","Td","Ah"]"#);
+        assert_eq!(serde_json::from_str::<CardSet>(&text).unwrap(), set);
+        assert!(serde_json::from_str::<CardSet>(r#"["Zz"]"#).is_err());
+    }
+}
@@ -1,18 +1,15 @@
-mod common;
-
-use crate::common::FromClientMessage;
 use message_io::network::{NetEvent, Transport};
 use message_io::node::{self, NodeEvent};
-use std::time::Duration;
+use pocker::command::{FromClientMessage, FromServerMessage};
+use std::io::stdin;
+use std::thread;
 
 enum Signal {
-    Greet,
-    // Any other app event here.
+    // 终端输入的一行
+    Input(String),
 }
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-
     let (handler, listener) = node::split();
 
     let (server, _) = handler
@@ -22,21 +19,65 @@ fn main() {
 
     listener.for_each(move |event| match event {
         NodeEvent::Network(net_event) => match net_event {
-            NetEvent::Connected(_endpoint, _ok) => handler.signals().send(Signal::Greet),
-            NetEvent::Accepted(_, _) => unreachable!(), // Only generated by listening
-            NetEvent::Message(_endpoint, data) => {
-                println!("Received: {}", String::from_utf8_lossy(data));
+            NetEvent::Connected(_endpoint, _ok) => {
+                // 命令行参数是之前分配的 id 时回到原来的座位
+                let id = std::env::args().nth(1).and_then(|arg| arg.parse().ok());
+                let output_data = bincode::serialize(&FromClientMessage::Hello(id)).unwrap();
+                handler.network().send(server, &output_data);
+                // 以 "say " 开头为聊天，"name " 开头为改名，其余作为行动发给服务器
+                let handler = handler.clone();
+                thread::spawn(move || {
+                    for line in stdin().lines() {
+                        let Ok(line) = line else { break };
+                        handler.signals().send(Signal::Input(line));
+                    }
+                });
             }
-            NetEvent::Disconnected(_endpoint) => (),
+            NetEvent::Accepted(_, _) => unreachable!(), // Only generated by listening
+            NetEvent::Message(_endpoint, data) => match bincode::deserialize(data) {
+                Ok(FromServerMessage::Info(text)) => println!("{}", text),
+                Ok(FromServerMessage::YourTurn(legal)) => {
+                    let actions = legal
+                        .actions()
+                        .iter()
+                        .map(|action| action.to_string())
+                        .collect::<Vec<String>>()
+                        .join("/");
+                    println!("Your turn: {}", actions);
+                }
+                Ok(FromServerMessage::Welcome(info)) => {
+                    println!("You are {} (reconnect with id {})", info, info.id)
+                }
+                Ok(FromServerMessage::Error(e)) => println!("Error: {}", e),
+                Ok(FromServerMessage::Players(players)) => {
+                    let names = players
+                        .iter()
+                        .map(|player| player.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    println!("Players: {}", names);
+                }
+                Ok(FromServerMessage::Table(view)) => println!("{}", view),
+                Ok(FromServerMessage::HandComplete(history)) => print!("{}", history),
+                Err(_) => println!("Received: {}", String::from_utf8_lossy(data)),
+            },
+            NetEvent::Disconnected(_endpoint) => handler.stop(),
         },
         NodeEvent::Signal(signal) => match signal {
-            Signal::Greet => {
-                let message = FromClientMessage::Chat("ha".to_string());
+            Signal::Input(line) => {
+                let message = if let Some(text) = line.strip_prefix("say ") {
+                    FromClientMessage::Chat(text.to_string())
+                } else if let Some(name) = line.strip_prefix("name ") {
+                    FromClientMessage::Profile {
+                        name: name.to_string(),
+                        avatar: None,
+                        country: None,
+                    }
+                } else {
+                    FromClientMessage::Command(line)
+                };
                 let output_data = bincode::serialize(&message).unwrap();
                 handler.network().send(server, &output_data);
-                handler
-                    .signals()
-                    .send_with_timer(Signal::Greet, Duration::from_secs(1));
             }
         },
     });
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+/*
+ * 行动计时: 每次决策有固定的思考时间，超出的部分从时间银行里扣
+ * 时间银行用完仍未行动即为超时，能过牌则自动过牌，否则自动弃牌
+ * 每手牌开始时时间银行补充 refill，最多补到 max_bank
+ * 这里只负责记账，不会打断正在思考的玩家；到点自动行动要调用方自己计时，
+ * 例如服务器按 `allowance` 设定时器，`Game::play_betting_round` 只在决策返回后判定超时
+ */
+#[derive(Debug, Clone)]
+pub struct ActionClock {
+    pub per_action: Duration,
+    pub max_bank: Duration,
+    pub refill: Duration,
+    // 按玩家 id 记录剩余的时间银行，没有记录的玩家时间银行是满的
+    banks: HashMap<u64, Duration>,
+}
+
+impl ActionClock {
+    pub fn new(per_action: Duration, max_bank: Duration, refill: Duration) -> Self {
+        Self {
+            per_action,
+            max_bank,
+            refill,
+            banks: HashMap::new(),
+        }
+    }
+
+    pub fn time_bank(&self, player: u64) -> Duration {
+        self.banks.get(&player).copied().unwrap_or(self.max_bank)
+    }
+
+    /// 玩家这次决策最多可以用的时间
+    pub fn allowance(&self, player: u64) -> Duration {
+        self.per_action + self.time_bank(player)
+    }
+
+    /*
+     * 记下玩家这次决策用的时间，超出思考时间的部分从时间银行扣除
+     * @return bool 是否超时
+     */
+    pub fn charge(&mut self, player: u64, elapsed: Duration) -> bool {
+        let bank = self.time_bank(player);
+        let overtime = elapsed.saturating_sub(self.per_action);
+        self.banks.insert(player, bank.saturating_sub(overtime));
+        overtime > bank
+    }
+
+    /// 新的一手牌，给所有玩家补充时间银行
+    pub fn refill_banks(&mut self) {
+        for bank in self.banks.values_mut() {
+            *bank = (*bank + self.refill).min(self.max_bank);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_bank() {
+        let mut clock = ActionClock::new(
+            Duration::from_secs(15),
+            Duration::from_secs(30),
+            Duration::from_secs(5),
+        );
+        assert_eq!(clock.allowance(1), Duration::from_secs(45));
+        assert!(!clock.charge(1, Duration::from_secs(10)));
+        assert_eq!(clock.time_bank(1), Duration::from_secs(30));
+        assert!(!clock.charge(1, Duration::from_secs(35)));
+        assert_eq!(clock.time_bank(1), Duration::from_secs(10));
+        assert!(clock.charge(1, Duration::from_secs(26)));
+        assert_eq!(clock.time_bank(1), Duration::ZERO);
+
+        clock.refill_banks();
+        assert_eq!(clock.time_bank(1), Duration::from_secs(5));
+        for _ in 0..10 {
+            clock.refill_banks();
+        }
+        assert_eq!(clock.time_bank(1), Duration::from_secs(30));
+        assert_eq!(clock.time_bank(2), Duration::from_secs(30));
+    }
+}
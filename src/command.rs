@@ -1,13 +1,36 @@
+use crate::action::LegalActions;
+use crate::controller::TableView;
+use crate::history::HandHistory;
+use crate::player::PlayerInfo;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum FromClientMessage {
+    // 连接后第一条消息，带上之前分配的 id 可以回到原来的座位
+    Hello(Option<u64>),
     Chat(String),
+    // 玩家行动，例如 "raise 60"
     Command(String),
+    // 设置自己的名字和资料，id 由服务器分配
+    Profile {
+        name: String,
+        avatar: Option<String>,
+        country: Option<String>,
+    },
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct FromServerMessage {
-    message_type: u8,
-    input_data: String,
+#[derive(Serialize, Deserialize, Debug)]
+pub enum FromServerMessage {
+    Info(String),
+    // 轮到该客户端行动
+    YourTurn(LegalActions),
+    Error(String),
+    // 入座后发送，id 用于断线重连
+    Welcome(PlayerInfo),
+    // 入座的玩家有变化时发送，按座位顺序
+    Players(Vec<PlayerInfo>),
+    // 该客户端看到的牌桌，包括自己的手牌、公共牌和亮出的手牌
+    Table(TableView),
+    // 一手牌结束后的完整记录，包括每组公共牌和分池结果
+    HandComplete(HandHistory),
 }
@@ -0,0 +1,279 @@
+use crate::action::{Action, LegalActions};
+use crate::card::Card;
+use crate::player::PlayerStatus;
+use crate::session::PurchaseOffer;
+use crate::utils::read_command;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+
+/// 其他玩家对外可见的信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerView {
+    // 全下亮牌后可以看到的手牌
+    pub shown: Option<Vec<Card>>,
+    pub id: u64,
+    pub name: String,
+    pub position: u32,
+    pub chips: u32,
+    pub status: PlayerStatus,
+}
+
+/// 轮到某位玩家行动时，牌桌的只读视图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableView {
+    pub seat: usize,
+    pub hand: Vec<Card>,
+    pub community_cards: Vec<Card>,
+    pub pot: u32,
+    pub current_bet: u32,
+    pub players: Vec<PlayerView>,
+}
+
+impl TableView {
+    pub fn me(&self) -> &PlayerView {
+        &self.players[self.seat]
+    }
+}
+
+impl fmt::Display for TableView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hand = self
+            .hand
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        let board = self
+            .community_cards
+            .iter()
+            .map(|card| card.to_string())
+            .collect::<Vec<String>>()
+            .join(", ");
+        write!(
+            f,
+            "{} hand: [{}] board: [{}] pot: {} to match: {} chips: {}",
+            self.me().name,
+            hand,
+            board,
+            self.pot,
+            self.current_bet,
+            self.me().chips
+        )?;
+        for player in &self.players {
+            if let (Some(shown), false) = (&player.shown, player.id == self.me().id) {
+                let shown = shown
+                    .iter()
+                    .map(|card| card.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, " | {}: [{}]", player.name, shown)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 玩家决策来源，`Game::play_betting_round` 轮到玩家行动时调用
+pub trait PlayerController {
+    fn decide(&mut self, view: &TableView, legal: &LegalActions) -> Action;
+
+    /// 是否接受重购/加购/补码，默认不买
+    fn accept_purchase(&mut self, _offer: &PurchaseOffer) -> bool {
+        false
+    }
+}
+
+/// 通过终端读取玩家输入
+pub struct TerminalController;
+
+impl PlayerController for TerminalController {
+    fn decide(&mut self, view: &TableView, legal: &LegalActions) -> Action {
+        println!("{}", view);
+
+        let prompt = format!(
+            "{} > ",
+            legal
+                .actions()
+                .iter()
+                .map(|action| action.to_string())
+                .collect::<Vec<String>>()
+                .join("/")
+        );
+        loop {
+            let (command, args) = read_command(&prompt);
+            let input = match args {
+                Some(args) => format!("{} {}", command, args.join(" ")),
+                None => command,
+            };
+            match input.parse().and_then(|action| legal.validate(action)) {
+                Ok(action) => return action,
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    fn accept_purchase(&mut self, offer: &PurchaseOffer) -> bool {
+        let prompt = format!("{} {} chips? (y/n) > ", offer.kind, offer.amount);
+        loop {
+            match read_command(&prompt).0.as_str() {
+                "y" | "yes" => return true,
+                "n" | "no" => return false,
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// 按预设脚本依次行动，脚本用完后过牌或跟注，用于测试
+#[derive(Default)]
+pub struct ScriptedController {
+    script: VecDeque<Action>,
+}
+
+impl ScriptedController {
+    pub fn new(script: Vec<Action>) -> Self {
+        Self {
+            script: script.into(),
+        }
+    }
+}
+
+impl PlayerController for ScriptedController {
+    fn decide(&mut self, view: &TableView, legal: &LegalActions) -> Action {
+        match self.script.pop_front() {
+            Some(action) => action,
+            None => PassiveBot.decide(view, legal),
+        }
+    }
+}
+
+/// 只会过牌或跟注的机器人
+pub struct PassiveBot;
+
+impl PlayerController for PassiveBot {
+    fn decide(&mut self, _view: &TableView, legal: &LegalActions) -> Action {
+        if legal.can_check() {
+            Action::Check
+        } else if legal.can_call() {
+            Action::Call
+        } else {
+            Action::AllIn
+        }
+    }
+}
+
+/// 随机选择可用行动的机器人
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new() -> Self {
+        Self {
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for RandomBot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlayerController for RandomBot {
+    fn decide(&mut self, _view: &TableView, legal: &LegalActions) -> Action {
+        // 能免费过牌时不弃牌
+        let actions: Vec<Action> = legal
+            .actions()
+            .into_iter()
+            .filter(|&action| !(action == Action::Fold && legal.can_check()))
+            .collect();
+        match *actions.choose(&mut self.rng).unwrap() {
+            Action::Bet(minimum) => Action::Bet(self.rng.gen_range(minimum..=legal.max_raise_to())),
+            Action::Raise(minimum) => {
+                Action::Raise(self.rng.gen_range(minimum..=legal.max_raise_to()))
+            }
+            action => action,
+        }
+    }
+}
+
+/// 测试用: 为 count 位玩家各创建一个控制器，make 的参数是玩家下标
+#[cfg(test)]
+pub(crate) fn boxed_controllers<C: PlayerController + 'static>(
+    count: usize,
+    make: impl FnMut(u64) -> C,
+) -> Vec<Box<dyn PlayerController>> {
+    (0..count as u64)
+        .map(make)
+        .map(|controller| Box::new(controller) as Box<dyn PlayerController>)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(current_bet: u32) -> TableView {
+        TableView {
+            seat: 0,
+            hand: vec![],
+            community_cards: vec![],
+            pot: 0,
+            current_bet,
+            players: vec![PlayerView {
+                shown: None,
+                id: 0,
+                name: "Player 0".to_string(),
+                position: 0,
+                chips: 100,
+                status: PlayerStatus::Betting(0),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_scripted_controller() {
+        let mut controller = ScriptedController::new(vec![Action::Raise(40)]);
+        let legal = LegalActions::new(100, 0, 20, 20);
+        assert_eq!(controller.decide(&view(20), &legal), Action::Raise(40));
+        assert_eq!(controller.decide(&view(20), &legal), Action::Call);
+    }
+
+    #[test]
+    fn test_passive_bot() {
+        let mut bot = PassiveBot;
+        assert_eq!(
+            bot.decide(&view(0), &LegalActions::new(100, 0, 0, 20)),
+            Action::Check
+        );
+        assert_eq!(
+            bot.decide(&view(500), &LegalActions::new(100, 0, 500, 500)),
+            Action::AllIn
+        );
+    }
+
+    #[test]
+    fn test_random_bot_picks_legal_action() {
+        let mut bot = RandomBot::with_seed(7);
+        let mut legal = LegalActions::new(100, 0, 20, 20);
+        for raise_limit in [u32::MAX, 40] {
+            legal.raise_limit = raise_limit;
+            for _ in 0..20 {
+                let action = bot.decide(&view(20), &legal);
+                assert!(legal.validate(action).is_ok());
+            }
+        }
+    }
+}
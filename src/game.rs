@@ -1,14 +1,110 @@
 // src/game.rs
+use crate::action::{Action, ActionError, LegalActions, PreAction};
+use crate::betting::{Ante, BettingStructure};
 use crate::card::{Card, Deck};
+use crate::clock::ActionClock;
+use crate::controller::{PlayerController, PlayerView, TableView};
 use crate::hand_rank::HandRank;
-use crate::player::{Player, PlayerStatus};
+use crate::history::{ActionRecord, HandHistory, PayoutRecord, SeatRecord};
+use crate::player::{MissedBlinds, Player, PlayerInfo, PlayerStatus};
+use crate::pot::{build_pots, split_pot, OddChipRule, Payout, Pot};
+use crate::seat::{SeatError, TableSize};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+/// 一手牌的阶段，Complete 表示没有进行中的牌局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HandPhase {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    Showdown,
+    Complete,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    NotYourTurn { expected: usize, player: usize },
+    NoActionPending,
+    BettingRoundOpen,
+    NoHandInProgress,
+    NotInHand(usize),
+    InvalidAction(ActionError),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameError::NotYourTurn { expected, player } => write!(
+                f,
+                "player {} acted out of turn, waiting for player {}",
+                player, expected
+            ),
+            GameError::NoActionPending => write!(f, "no player is due to act"),
+            GameError::BettingRoundOpen => write!(f, "the betting round is still open"),
+            GameError::NoHandInProgress => write!(f, "no hand in progress"),
+            GameError::NotInHand(player) => write!(f, "player {} is not in the hand", player),
+            GameError::InvalidAction(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<ActionError> for GameError {
+    fn from(e: ActionError) -> Self {
+        GameError::InvalidAction(e)
+    }
+}
 
 pub struct Game {
     pub deck: Deck,
+    // 按座位号顺时针排列，空座位不在其中
     pub players: Vec<Player>,
+    // 牌桌的座位数
+    pub seats: u32,
     pub community_cards: Vec<Card>,
     pub pot: u32,
-    pub small_blind_position: usize,
+    // 庄家按钮所在的玩家，每手牌结束后顺时针移动
+    pub button: usize,
+    // 死按钮规则: 大盲每手牌只往前移一位，空出的小盲位和按钮位不补
+    pub dead_button: bool,
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub odd_chip_rule: OddChipRule,
+    pub betting: BettingStructure,
+    pub ante: Ante,
+    // 枪口位 (UTG) 是否下两倍大盲的强制盲注，翻牌前最后行动
+    pub straddle: bool,
+    // 行动计时，None 表示不限时
+    pub clock: Option<ActionClock>,
+    // 全下后没有人能再下注时，剩下的公共牌发几次，每次各分一份底池
+    pub runs: u32,
+    // 多发的几组公共牌，第一组就是 community_cards
+    pub runouts: Vec<Vec<Card>>,
+    // 全下后所有玩家亮牌
+    pub exposed: bool,
+    // 下一手为炸弹底池时每人的底注，所有人下底注后直接从翻牌开始，开始后清空
+    pub bomb_pot: Option<u32>,
+    pub phase: HandPhase,
+    // 本轮最高下注和最小加注幅度
+    pub current_bet: u32,
+    pub min_raise: u32,
+    // 本轮完整下注/加注的次数，翻牌前大盲算一次
+    pub raises: u32,
+    // 上一手牌的结果
+    pub payouts: Vec<Payout>,
+    // 当前或上一手牌的记录
+    pub history: HandHistory,
+    to_act: Option<usize>,
+    // 玩家本轮最后一次行动时的最高下注，None 表示本轮还没行动
+    acted_at: Vec<Option<u32>>,
+    // 上一手牌大盲的座位号
+    last_big_blind: Option<u32>,
+    // 死按钮规则下小盲位空出，这一手不下小盲
+    small_blind_dead: bool,
 }
 
 const SMALL_BLIND: u32 = 10;
@@ -18,20 +114,144 @@ impl Game {
         let mut deck = Deck::new();
         deck.shuffle();
         let mut players = Vec::with_capacity(player_count);
-        for _ in 0..player_count {
-            players.push(Player::new(initial_chips));
+        for seat in 0..player_count {
+            let mut player = Player::new(initial_chips);
+            player.info = PlayerInfo::new(seat as u64, &format!("Player {}", seat));
+            player.position = seat as u32;
+            players.push(player);
         }
         Self {
             deck,
             players,
+            seats: player_count as u32,
             community_cards: Vec::with_capacity(5),
             pot: 0,
-            small_blind_position: 0,
+            button: 0,
+            dead_button: false,
+            small_blind: SMALL_BLIND,
+            big_blind: SMALL_BLIND * 2,
+            odd_chip_rule: OddChipRule::default(),
+            betting: BettingStructure::default(),
+            ante: Ante::default(),
+            straddle: false,
+            clock: None,
+            runs: 1,
+            runouts: Vec::new(),
+            exposed: false,
+            bomb_pot: None,
+            phase: HandPhase::Complete,
+            current_bet: 0,
+            min_raise: SMALL_BLIND * 2,
+            raises: 0,
+            payouts: Vec::new(),
+            history: HandHistory::default(),
+            to_act: None,
+            acted_at: Vec::new(),
+            last_big_blind: None,
+            small_blind_dead: false,
+        }
+    }
+
+    /// 没有玩家的空桌
+    pub fn with_table_size(size: TableSize) -> Self {
+        let mut game = Self::new(0, 0);
+        game.seats = size.seats();
+        game
+    }
+
+    /// 坐在 `seat` 号座位的玩家
+    pub fn player_at(&self, seat: u32) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|player| player.position == seat)
+    }
+
+    pub fn free_seats(&self) -> Vec<u32> {
+        (0..self.seats)
+            .filter(|&seat| self.player_at(seat).is_none())
+            .collect()
+    }
+
+    /*
+     * 新玩家带 chips 筹码入座，seat 为 None 时坐第一个空座位
+     * 已经打过一手牌时，新玩家要补一个大盲才能参与
+     * @return usize 玩家的下标
+     */
+    pub fn join(
+        &mut self,
+        seat: Option<u32>,
+        info: PlayerInfo,
+        chips: u32,
+    ) -> Result<usize, SeatError> {
+        let mut player = Player::new(chips);
+        player.info = info;
+        if self.last_big_blind.is_some() && self.funded_players() >= 2 {
+            player.missed_blinds = MissedBlinds::BigBlind;
+        }
+        self.sit(seat, player)
+    }
+
+    /// 让已有的玩家入座，按座位号插入并保持按钮在原来的玩家身上
+    pub fn sit(&mut self, seat: Option<u32>, mut player: Player) -> Result<usize, SeatError> {
+        if self.phase != HandPhase::Complete {
+            return Err(SeatError::HandInProgress);
+        }
+        let seat = match seat {
+            Some(seat) if seat >= self.seats => return Err(SeatError::NoSuchSeat(seat)),
+            Some(seat) if self.player_at(seat).is_some() => return Err(SeatError::SeatTaken(seat)),
+            Some(seat) => seat,
+            None => *self.free_seats().first().ok_or(SeatError::TableFull)?,
+        };
+        player.position = seat;
+        let index = self
+            .players
+            .iter()
+            .position(|other| other.position > seat)
+            .unwrap_or(self.players.len());
+        if index <= self.button && !self.players.is_empty() {
+            self.button += 1;
+        }
+        self.players.insert(index, player);
+        Ok(index)
+    }
+
+    /// `seat` 号座位的玩家离座，按钮退到上一位玩家，下一手的盲注位置不变
+    pub fn leave(&mut self, seat: u32) -> Result<Player, SeatError> {
+        if self.phase != HandPhase::Complete {
+            return Err(SeatError::HandInProgress);
+        }
+        let index = self.player_at(seat).ok_or(SeatError::EmptySeat(seat))?;
+        let player = self.players.remove(index);
+        let count = self.players.len();
+        if count == 0 {
+            self.button = 0;
+        } else if index <= self.button {
+            self.button = (self.button + count - 1) % count;
         }
+        Ok(player)
     }
 
+    /// 暂离或回到牌局，下一手牌开始时生效
+    pub fn set_sitting_out(&mut self, seat: u32, sitting_out: bool) -> Result<(), SeatError> {
+        let index = self.player_at(seat).ok_or(SeatError::EmptySeat(seat))?;
+        self.players[index].sitting_out = sitting_out;
+        Ok(())
+    }
+
+    /// 有筹码且没有暂离的玩家数
+    pub fn active_players(&self) -> usize {
+        self.players
+            .iter()
+            .filter(|player| player.can_play())
+            .count()
+    }
+
+    /// 给有筹码的玩家发手牌，没有筹码或暂离的玩家坐在一旁不参与
     pub fn deal_to_players(&mut self) {
         for player in &mut self.players {
+            if !player.can_play() {
+                continue;
+            }
             let hand_card = (self.deck.deal(), self.deck.deal());
             player.receive_card(hand_card);
         }
@@ -41,131 +261,778 @@ impl Game {
         self.community_cards.push(self.deck.deal());
     }
 
-    pub fn blind(&mut self) {}
+    /// 从 from 开始顺时针的下一位拿到手牌的玩家
+    pub fn next_player(&self, from: usize) -> usize {
+        let count = self.players.len();
+        (1..=count)
+            .map(|step| (from + step) % count)
+            .find(|&i| self.players[i].status != PlayerStatus::Waiting)
+            .unwrap_or(from)
+    }
 
-    pub fn place_bets(&mut self, first_round: bool) {
-        let mut active_players: Vec<&mut Player> = self
+    /// 小盲位，单挑时由按钮下小盲
+    pub fn small_blind_seat(&self) -> usize {
+        let dealt_in = self
             .players
-            .iter_mut()
-            .filter(|player| matches!(player.status, PlayerStatus::Betting(_)))
-            .collect();
+            .iter()
+            .filter(|player| player.status != PlayerStatus::Waiting)
+            .count();
+        if dealt_in == 2 {
+            self.button
+        } else {
+            self.next_player(self.button)
+        }
+    }
+
+    pub fn big_blind_seat(&self) -> usize {
+        if self.small_blind_dead {
+            self.next_player(self.button)
+        } else {
+            self.next_player(self.small_blind_seat())
+        }
+    }
+
+    /// 小盲位和大盲位下盲注，筹码不足时全下；大盲前注在大盲之后下
+    pub fn blind(&mut self) {
+        let small_blind_seat = self.small_blind_seat();
+        let big_blind_seat = self.big_blind_seat();
+        if !self.small_blind_dead {
+            self.players[small_blind_seat].commit(self.small_blind);
+        }
+        self.players[big_blind_seat].commit(self.big_blind);
+        if let Ante::BigBlind(ante) = self.ante {
+            self.pot += self.players[big_blind_seat].post_ante(ante);
+        }
+        self.last_big_blind = Some(self.players[big_blind_seat].position);
+    }
 
-        let mut current_rasie_position = 0;
-        let mut mini_bet = match active_players[0].status {
-            PlayerStatus::Betting(s) => s,
-            _ => unreachable!(),
+    /*
+     * 大盲从 from 号座位移到 to 号座位，中间跳过的暂离玩家错过了大盲
+     * 之前已经错过大盲的，这次算作大小盲都错过
+     */
+    fn mark_missed_blinds(&mut self, from: u32, to: u32) {
+        let passed = |seat: u32| match from.cmp(&to) {
+            std::cmp::Ordering::Less => from < seat && seat < to,
+            std::cmp::Ordering::Greater => seat > from || seat < to,
+            std::cmp::Ordering::Equal => false,
         };
+        for player in &mut self.players {
+            if player.sitting_out && player.chips > 0 && passed(player.position) {
+                player.missed_blinds = match player.missed_blinds {
+                    MissedBlinds::None => MissedBlinds::BigBlind,
+                    _ => MissedBlinds::Both,
+                };
+            }
+        }
+    }
 
-        // blinds
-        if first_round {
-            println!("before blinds");
-            for player in &active_players {
-                player.show_hand()
-            }
-
-            active_players[0].chips -= SMALL_BLIND;
-            active_players[0].status = PlayerStatus::Betting(SMALL_BLIND);
-            active_players[1].chips -= SMALL_BLIND * 2;
-            active_players[1].status = PlayerStatus::Betting(SMALL_BLIND * 2);
-            current_rasie_position = 2;
-            mini_bet = SMALL_BLIND * 2;
-            println!("after blinds");
-            for player in &active_players {
-                player.show_hand()
-            }
-        }
-
-        //TODO 根据current_rasie_position 判断下注轮次
-        loop {
-            active_players.rotate_left(current_rasie_position);
-            current_rasie_position = 0;
-            active_players.retain(|player| matches!(player.status, PlayerStatus::Betting(_)));
-            for (i, player) in active_players.iter_mut().enumerate() {
-                let num = player.place_bet(mini_bet);
-                if num > mini_bet {
-                    // raise
-                    current_rasie_position = i;
-                    mini_bet = num;
-                }
+    /*
+     * 错过盲注的玩家补盲注，大盲是活的，算作本轮下注，小盲是死筹码
+     * 这一手正好在盲注位上的玩家不用补
+     */
+    fn post_missed_blinds(&mut self) {
+        let big_blind_seat = self.big_blind_seat();
+        let small_blind_seat = (!self.small_blind_dead).then(|| self.small_blind_seat());
+        for i in 0..self.players.len() {
+            let player = &mut self.players[i];
+            if player.status == PlayerStatus::Waiting {
+                continue;
+            }
+            let missed = std::mem::take(&mut player.missed_blinds);
+            if i == big_blind_seat || Some(i) == small_blind_seat {
+                continue;
+            }
+            if missed == MissedBlinds::Both {
+                self.pot += player.post_ante(self.small_blind);
             }
-            if current_rasie_position == 0 {
-                break;
+            if missed != MissedBlinds::None {
+                player.commit(self.big_blind);
             }
         }
-        if first_round {
-            println!("after first round");
-            for player in &active_players {
-                player.show_hand()
+    }
+
+    /// 所有拿到手牌的玩家各下一份前注
+    fn post_antes(&mut self, ante: u32) {
+        for player in &mut self.players {
+            if player.status != PlayerStatus::Waiting {
+                self.pot += player.post_ante(ante);
             }
         }
     }
 
-    pub fn show_community_cards(&self) {
-        let result = self
-            .community_cards
+    /// 抓位玩家，不少于三人时由大盲左手边的玩家抓
+    pub fn straddle_seat(&self) -> Option<usize> {
+        let dealt_in = self
+            .players
+            .iter()
+            .filter(|player| player.status != PlayerStatus::Waiting)
+            .count();
+        let seat = self.next_player(self.big_blind_seat());
+        if self.straddle && dealt_in >= 3 && self.players[seat].chips > 0 {
+            Some(seat)
+        } else {
+            None
+        }
+    }
+
+    /// 按钮顺时针移动到下一位玩家，死按钮规则在开始下一手时再调整
+    pub fn move_button(&mut self) {
+        let count = self.players.len();
+        self.button = (1..=count)
+            .map(|step| (self.button + step) % count)
+            .find(|&i| self.players[i].can_play())
+            .unwrap_or((self.button + 1) % count);
+    }
+
+    /*
+     * 死按钮规则: 上一手大盲左手边第一位有筹码的玩家下大盲，
+     * 上一手的大盲还在座位上就下小盲，否则小盲空缺；
+     * 按钮放在小盲（或空缺时大盲）右手边的玩家上，行动顺序和按钮留在空座位上相同
+     * 在开始一手牌时计算，两手牌之间入座离座的玩家都会算进去
+     */
+    fn move_big_blind(&mut self, last_big_blind: u32) {
+        let funded = self.funded_indices();
+        let big_blind = self.dead_button_big_blind(&funded, last_big_blind);
+        let small_blind = self
+            .player_at(last_big_blind)
+            .filter(|&i| self.players[i].can_play() && i != big_blind);
+        self.small_blind_dead = small_blind.is_none();
+        let first_blind = small_blind.unwrap_or(big_blind);
+        let before = funded.iter().position(|&i| i == first_blind).unwrap();
+        self.button = funded[(before + funded.len() - 1) % funded.len()];
+    }
+
+    /// 能打下一手牌的玩家的下标
+    fn funded_indices(&self) -> Vec<usize> {
+        (0..self.players.len())
+            .filter(|&i| self.players[i].can_play())
+            .collect()
+    }
+
+    /// 死按钮规则下，上一手大盲左手边第一位能打的玩家下大盲
+    fn dead_button_big_blind(&self, funded: &[usize], last_big_blind: u32) -> usize {
+        funded
             .iter()
-            .map(|card| card.to_string())
-            .collect::<Vec<String>>()
-            .join(", ");
-        println!("{}", result);
+            .copied()
+            .find(|&i| self.players[i].position > last_big_blind)
+            .unwrap_or(funded[0])
     }
 
-    pub fn play_round(&mut self) {
-        //check balance
-        //TODO: 余额不足需要购买筹码
-        for player in &self.players {
-            if player.chips == 0 {
-                println!("Player {} is out of chips", player.position);
-            }
+    /*
+     * 两手牌之间推算下一手的大盲位，和 `start_hand` 的规则一致:
+     * 单挑时按钮（或按钮没人时的下一位）下小盲，否则按钮左手边依次下小盲、大盲，
+     * 死按钮规则下大盲跟着上一手的大盲移动
+     * @return Option<usize> 能打的玩家不足两人时为 None
+     */
+    pub fn next_big_blind_seat(&self) -> Option<usize> {
+        let funded = self.funded_indices();
+        if funded.len() < 2 {
+            return None;
         }
+        if let (true, Some(last_big_blind), 3..) =
+            (self.dead_button, self.last_big_blind, funded.len())
+        {
+            return Some(self.dead_button_big_blind(&funded, last_big_blind));
+        }
+        let after_button = funded.iter().position(|&i| i > self.button).unwrap_or(0);
+        let button_funded = self.players[self.button].can_play();
+        let steps = if funded.len() == 2 && button_funded {
+            0
+        } else {
+            1
+        };
+        Some(funded[(after_button + steps) % funded.len()])
+    }
 
+    /// 还有筹码、能继续打下一手的玩家数
+    pub fn funded_players(&self) -> usize {
+        self.players
+            .iter()
+            .filter(|player| player.chips > 0)
+            .count()
+    }
+
+    /// 为第 `seat` 位玩家生成牌桌的只读视图
+    pub fn view(&self, seat: usize) -> TableView {
+        TableView {
+            seat,
+            hand: self.players[seat].hand.clone(),
+            community_cards: self.community_cards.clone(),
+            pot: self.pot,
+            current_bet: self.current_bet,
+            players: self
+                .players
+                .iter()
+                .map(|player| PlayerView {
+                    shown: (self.exposed
+                        && matches!(
+                            player.status,
+                            PlayerStatus::Betting(_) | PlayerStatus::Allin(_)
+                        ))
+                    .then(|| player.hand.clone()),
+                    id: player.info.id,
+                    name: player.info.name.clone(),
+                    position: player.position,
+                    chips: player.chips,
+                    status: player.status,
+                })
+                .collect(),
+        }
+    }
+
+    /// 开始新的一手牌: 洗牌、发手牌、下盲注，进入翻牌前下注
+    pub fn start_hand(&mut self) {
+        self.deck.shuffle();
+        self.community_cards.clear();
+        self.runouts.clear();
+        self.exposed = false;
+        self.payouts.clear();
+        for player in &mut self.players {
+            player.reset_bet();
+        }
+        if let Some(clock) = &mut self.clock {
+            clock.refill_banks();
+        }
         self.deal_to_players();
-        for player in &self.players {
-            player.show_hand();
+        // 单挑时按钮下小盲，按钮上的玩家没拿到手牌就把按钮交给下一位拿到手牌的玩家
+        if self.players[self.button].status == PlayerStatus::Waiting
+            && self.remaining_players().len() == 2
+        {
+            self.button = self.next_player(self.button);
         }
+        self.history = HandHistory::new(
+            self.deck.seed,
+            self.players
+                .iter()
+                .filter(|player| player.status != PlayerStatus::Waiting)
+                .map(|player| SeatRecord {
+                    seat: player.position,
+                    player: player.info.clone(),
+                    chips: player.chips,
+                })
+                .collect(),
+        );
 
-        //pre-flop betting
-        self.place_bets(true);
+        // 炸弹底池: 不下盲注，所有人下底注后直接发翻牌
+        if let Some(ante) = self.bomb_pot.take() {
+            self.post_antes(ante);
+            for _ in 0..3 {
+                self.deal_community_card();
+            }
+            self.phase = HandPhase::Flop;
+            self.start_street(0, self.button);
+            return;
+        }
 
-        //flop
-        for _ in 0..3 {
+        self.small_blind_dead = false;
+        if let (true, Some(last_big_blind), 3..) =
+            (self.dead_button, self.last_big_blind, self.active_players())
+        {
+            self.move_big_blind(last_big_blind);
+        }
+        if let Ante::PerPlayer(ante) = self.ante {
+            self.post_antes(ante);
+        }
+        let last_big_blind = self.last_big_blind;
+        self.blind();
+        if let (Some(from), Some(to)) = (last_big_blind, self.last_big_blind) {
+            self.mark_missed_blinds(from, to);
+        }
+        self.post_missed_blinds();
+        self.phase = HandPhase::Preflop;
+        match self.straddle_seat() {
+            Some(seat) => {
+                self.players[seat].commit(self.big_blind * 2);
+                let straddle = self.players[seat].bet().max(self.big_blind);
+                self.start_street(straddle, seat);
+            }
+            None => self.start_street(self.big_blind, self.big_blind_seat()),
+        }
+    }
+
+    /*
+     * 开始一轮下注，从 after 左手边的玩家开始行动
+     * 最小加注幅度不小于最大的盲注（抓位时为抓的金额）
+     */
+    fn start_street(&mut self, current_bet: u32, after: usize) {
+        self.current_bet = current_bet;
+        let late_street = matches!(self.phase, HandPhase::Turn | HandPhase::River);
+        self.min_raise = self
+            .betting
+            .bet_size(self.big_blind, late_street)
+            .max(current_bet);
+        self.raises = u32::from(current_bet > 0);
+        self.acted_at = vec![None; self.players.len()];
+        for player in &mut self.players {
+            player.pre_action = None;
+        }
+        self.to_act = self.next_to_act(after);
+    }
+
+    /// 当前轮到行动的玩家，None 表示本轮下注已结束
+    pub fn to_act(&self) -> Option<usize> {
+        self.to_act
+    }
+
+    /// 当前行动玩家的合法行动
+    pub fn legal_actions(&self) -> Option<LegalActions> {
+        self.to_act.map(|i| self.legal_actions_for(i))
+    }
+
+    /*
+     * 玩家行动后如果面对的加注不足一个完整加注（全下筹码不够），
+     * 则不能再加注，只能跟注或弃牌
+     * 下注上限由下注结构决定
+     */
+    fn legal_actions_for(&self, i: usize) -> LegalActions {
+        let mut legal = self.players[i].legal_actions(self.current_bet, self.min_raise);
+        legal.raise_closed = match self.acted_at[i] {
+            Some(level) => self.current_bet - level < self.min_raise,
+            None => false,
+        };
+        let pot = self.pot + self.players.iter().map(|p| p.bet()).sum::<u32>();
+        legal.raise_limit = self.betting.raise_limit(&legal, pot, self.raises);
+        legal
+    }
+
+    /*
+     * 从 from 左手边开始找下一位需要行动的玩家
+     * 需要行动: 还没跟到最高下注，或者本轮还没行动过且还有其他能行动的玩家
+     * 大盲在翻牌前没有行动过，因此即使没人加注也有一次选择权
+     */
+    fn next_to_act(&self, from: usize) -> Option<usize> {
+        if self.remaining_players().len() <= 1 {
+            return None;
+        }
+        let betting = self
+            .players
+            .iter()
+            .filter(|player| matches!(player.status, PlayerStatus::Betting(_)))
+            .count();
+        let count = self.players.len();
+        (1..=count)
+            .map(|step| (from + step) % count)
+            .find(|&i| match self.players[i].status {
+                PlayerStatus::Betting(bet) => {
+                    bet < self.current_bet || (self.acted_at[i].is_none() && betting > 1)
+                }
+                _ => false,
+            })
+    }
+
+    /// 执行当前行动玩家的行动
+    pub fn apply_action(&mut self, player: usize, action: Action) -> Result<(), GameError> {
+        let Some(expected) = self.to_act else {
+            return Err(GameError::NoActionPending);
+        };
+        if expected != player {
+            return Err(GameError::NotYourTurn { expected, player });
+        }
+        let legal = self.legal_actions_for(player);
+        let bet = self.players[player].place_bet(action, &legal)?;
+        self.history.actions.push(ActionRecord {
+            phase: self.phase,
+            player: self.players[player].info.id,
+            action,
+        });
+        if bet > self.current_bet {
+            // 完整加注才更新最小加注幅度，不足额的全下只提高需要跟注的金额
+            if bet - self.current_bet >= self.min_raise {
+                self.raises += 1;
+            }
+            self.min_raise = self.min_raise.max(bet - self.current_bet);
+            self.current_bet = bet;
+        }
+        self.acted_at[player] = Some(self.current_bet);
+        self.to_act = self.next_to_act(player);
+        Ok(())
+    }
+
+    /// 设置或取消玩家本轮的预选行动，只对还能行动的玩家有效
+    pub fn set_pre_action(
+        &mut self,
+        player: usize,
+        pre_action: Option<PreAction>,
+    ) -> Result<(), GameError> {
+        if self.phase == HandPhase::Complete {
+            return Err(GameError::NoHandInProgress);
+        }
+        if !matches!(self.players[player].status, PlayerStatus::Betting(_)) {
+            return Err(GameError::NotInHand(player));
+        }
+        self.players[player].pre_action = pre_action;
+        Ok(())
+    }
+
+    /*
+     * 轮到的玩家有预选行动时替他执行，返回执行的行动
+     * 预选行动已经作废（下注额变了）时丢弃，返回 None 由玩家自己决定
+     */
+    pub fn apply_pre_action(&mut self) -> Option<(usize, Action)> {
+        let i = self.to_act?;
+        let pre_action = self.players[i].pre_action.take()?;
+        let legal = self.legal_actions_for(i);
+        let action = pre_action.resolve(&legal)?;
+        self.apply_action(i, action).ok()?;
+        Some((i, action))
+    }
+
+    /*
+     * 本轮下注结束后推进牌局
+     * 只剩一位玩家时直接结束，否则发下一条街的公共牌，河牌后进入摊牌
+     */
+    pub fn advance(&mut self) -> Result<HandPhase, GameError> {
+        if self.to_act.is_some() {
+            return Err(GameError::BettingRoundOpen);
+        }
+        match self.phase {
+            HandPhase::Complete => return Err(GameError::NoHandInProgress),
+            HandPhase::Showdown => {
+                let payouts = self.showdown();
+                self.finish_hand(payouts);
+            }
+            _ if self.remaining_players().len() == 1 => {
+                let payouts = self.award_uncontested();
+                self.finish_hand(payouts);
+            }
+            _ if self.betting_closed() => {
+                self.collect_bets();
+                self.run_out();
+            }
+            street => {
+                self.collect_bets();
+                let (next, cards) = match street {
+                    HandPhase::Preflop => (HandPhase::Flop, 3),
+                    HandPhase::Flop => (HandPhase::Turn, 1),
+                    HandPhase::Turn => (HandPhase::River, 1),
+                    _ => (HandPhase::Showdown, 0),
+                };
+                for _ in 0..cards {
+                    self.deal_community_card();
+                }
+                self.phase = next;
+                if next != HandPhase::Showdown {
+                    self.start_street(0, self.button);
+                }
+            }
+        }
+        Ok(self.phase)
+    }
+
+    /// 还有两位以上玩家，但最多一位还能下注，之后不会再有下注
+    pub fn betting_closed(&self) -> bool {
+        let can_bet = self
+            .players
+            .iter()
+            .filter(|player| matches!(player.status, PlayerStatus::Betting(_)) && player.chips > 0)
+            .count();
+        self.remaining_players().len() >= 2 && can_bet <= 1
+    }
+
+    /*
+     * 没有人能再下注时所有玩家亮牌，直接发完公共牌进入摊牌
+     * runs 大于 1 时剩下的公共牌另外再发 runs - 1 组
+     */
+    fn run_out(&mut self) {
+        self.exposed = true;
+        for i in self.remaining_players() {
+            let player = &self.players[i];
+            println!("{} shows {}{}", player.info, player.hand[0], player.hand[1]);
+        }
+        let shared = self.community_cards.clone();
+        let missing = 5 - shared.len();
+        for _ in 0..missing {
             self.deal_community_card();
         }
-        self.place_bets(false);
+        if missing > 0 {
+            for _ in 1..self.runs {
+                if self.deck.cards.len() < missing {
+                    break;
+                }
+                let mut board = shared.clone();
+                for _ in 0..missing {
+                    board.push(self.deck.deal());
+                }
+                self.runouts.push(board);
+            }
+        }
+        self.phase = HandPhase::Showdown;
+    }
 
-        // Turn
-        self.deal_community_card();
-        self.place_bets(false);
+    /// 本手牌所有的公共牌组
+    pub fn boards(&self) -> Vec<Vec<Card>> {
+        std::iter::once(self.community_cards.clone())
+            .chain(self.runouts.iter().cloned())
+            .collect()
+    }
 
-        // River
-        self.deal_community_card();
-        self.place_bets(false);
+    /// 一手牌结束，记下结果
+    fn finish_hand(&mut self, payouts: Vec<Payout>) {
+        self.history.boards = self.boards();
+        self.history.payouts = payouts
+            .iter()
+            .map(|payout| PayoutRecord {
+                pot: payout.pot,
+                run: payout.run,
+                player: self.players[payout.player].info.id,
+                amount: payout.amount,
+            })
+            .collect();
+        self.payouts = payouts;
+        self.phase = HandPhase::Complete;
+    }
 
-        self.show_community_cards();
+    /*
+     * 让控制器为当前行动玩家做决策，直到本轮下注结束
+     * 非法行动或超时时能过牌则过牌，否则弃牌
+     * `decide` 是同步调用，这里不会中途打断它: 用时在返回后才计算，
+     * 超时的决策作废并换成超时行动，但牌局会一直等到它返回；
+     * 需要到点就替玩家行动的场合（网络对局）由服务器用计时器处理
+     */
+    pub fn play_betting_round(&mut self, controllers: &mut [Box<dyn PlayerController>]) {
+        while let (Some(i), Some(legal)) = (self.to_act, self.legal_actions()) {
+            if self.apply_pre_action().is_some() {
+                continue;
+            }
+            let started = Instant::now();
+            let mut action = controllers[i].decide(&self.view(i), &legal);
+            let id = self.players[i].info.id;
+            if let Some(clock) = &mut self.clock {
+                if clock.charge(id, started.elapsed()) {
+                    println!("{} ran out of time", self.players[i].info);
+                    action = Self::timeout_action(&legal);
+                }
+            }
+            if let Err(e) = self.apply_action(i, action) {
+                println!("{} illegal action {}: {}", self.players[i].info, action, e);
+                self.apply_action(i, Self::timeout_action(&legal)).unwrap();
+            }
+        }
+    }
+
+    /// 超时或非法行动时代替玩家做的行动: 能过牌则过牌，否则弃牌
+    pub fn timeout_action(legal: &LegalActions) -> Action {
+        if legal.can_check() {
+            Action::Check
+        } else {
+            Action::Fold
+        }
+    }
+
+    /// 本轮下注结束，将所有下注收入底池
+    pub fn collect_bets(&mut self) {
+        for player in &mut self.players {
+            self.pot += player.collect_bet();
+        }
+    }
+
+    /// 还没有弃牌的玩家
+    pub fn remaining_players(&self) -> Vec<usize> {
+        (0..self.players.len())
+            .filter(|&i| {
+                matches!(
+                    self.players[i].status,
+                    PlayerStatus::Betting(_) | PlayerStatus::Allin(_)
+                )
+            })
+            .collect()
+    }
 
-        let winner = self.determine_winner();
-        println!("Winner is player {}", winner);
+    /// 其他玩家都已弃牌，最后一位玩家不需摊牌直接赢得底池
+    pub fn award_uncontested(&mut self) -> Vec<Payout> {
+        self.collect_bets();
+        let winner = self.remaining_players()[0];
+        let payout = Payout {
+            pot: 0,
+            run: 0,
+            player: winner,
+            amount: self.pot,
+        };
+        println!(
+            "{} wins {} uncontested",
+            self.players[winner].info, payout.amount
+        );
+        self.players[winner].chips += self.pot;
+        self.pot = 0;
+        vec![payout]
+    }
+
+    /// 按玩家本手投入拆分主池和边池
+    pub fn pots(&self) -> Vec<Pot> {
+        let contributions: Vec<(u32, bool)> = self
+            .players
+            .iter()
+            .map(|player| {
+                (
+                    player.total_bet,
+                    matches!(
+                        player.status,
+                        PlayerStatus::Folded(_) | PlayerStatus::Waiting
+                    ),
+                )
+            })
+            .collect();
+        build_pots(&contributions)
+    }
+
+    /*
+     * 摊牌，每个底池分别比牌，平局时平分，返回每位玩家赢得的筹码
+     * 发了多组公共牌时每个底池按组数平分，每组各自比牌，除不尽的零头归第一组
+     */
+    pub fn showdown(&mut self) -> Vec<Payout> {
+        self.collect_bets();
+        let boards = self.boards();
+        let runs = boards.len() as u32;
+        let mut payouts = Vec::new();
+        for (i, pot) in self.pots().into_iter().enumerate() {
+            for (run, board) in boards.iter().enumerate() {
+                let share = pot.amount / runs + if run == 0 { pot.amount % runs } else { 0 };
+                let mut winners = self.winners_on(&pot.eligible, board);
+                self.order_for_odd_chips(&mut winners);
+                for mut payout in split_pot(i, share, &winners) {
+                    payout.run = run;
+                    println!(
+                        "Pot {}: {} wins {}",
+                        payout.pot, self.players[payout.player].info, payout.amount
+                    );
+                    self.players[payout.player].chips += payout.amount;
+                    self.pot -= payout.amount;
+                    payouts.push(payout);
+                }
+            }
+        }
+        payouts
+    }
+
+    /// 按零头规则给赢家排序，排在前面的先拿零头
+    fn order_for_odd_chips(&self, winners: &mut [usize]) {
+        match self.odd_chip_rule {
+            OddChipRule::LeftOfButton => {
+                let count = self.players.len();
+                winners.sort_by_key(|&i| (i + count - self.button - 1) % count);
+            }
+            OddChipRule::HighCardBySuit => {
+                winners.sort_by_key(|&i| {
+                    let high_card = self.players[i]
+                        .hand
+                        .iter()
+                        .map(|card| (card.rank, card.suit as u8))
+                        .max();
+                    std::cmp::Reverse(high_card)
+                });
+            }
+        }
+    }
+
+    /// 牌桌上的筹码总数: 玩家筹码 + 本轮下注 + 底池，一手牌中保持不变
+    pub fn total_chips(&self) -> u32 {
+        self.pot
+            + self
+                .players
+                .iter()
+                .map(|player| player.chips + player.bet())
+                .sum::<u32>()
+    }
+
+    pub fn show_community_cards(&self) {
+        for board in self.boards() {
+            let result = board
+                .iter()
+                .map(|card| card.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            println!("{}", result);
+        }
+    }
+
+    /// 用控制器打完一手牌，结束后移动按钮
+    pub fn play_round(&mut self, controllers: &mut [Box<dyn PlayerController>]) -> Vec<Payout> {
+        //check balance
+        for player in &self.players {
+            if player.chips == 0 {
+                println!("{} is out of chips and sits out", player.info);
+            } else if player.sitting_out {
+                println!("{} is sitting out", player.info);
+            }
+        }
+
+        self.start_hand();
+        if let Some(seed) = self.deck.seed {
+            println!("Seed: {}", seed);
+        }
+        for player in &self.players {
+            if player.status != PlayerStatus::Waiting {
+                player.show_hand();
+            }
+        }
+        while self.phase != HandPhase::Complete {
+            self.play_betting_round(controllers);
+            if self.advance().unwrap() == HandPhase::Showdown {
+                self.show_community_cards();
+            }
+        }
+        self.move_button();
+        self.payouts.clone()
     }
 
-    pub fn determine_winner(&self) -> usize {
+    /// 在有资格的玩家中比牌，返回所有牌力最大的赢家
+    pub fn determine_winners(&self, eligible: &[usize]) -> Vec<usize> {
+        self.winners_on(eligible, &self.community_cards)
+    }
+
+    fn winners_on(&self, eligible: &[usize], board: &[Card]) -> Vec<usize> {
         let mut best_hand = HandRank::HighCard(0, 0, 0, 0, 0);
-        let mut winner = 0;
+        let mut winners = Vec::new();
 
-        for (i, player) in self.players.iter().enumerate() {
-            let hand_rank = player.best_hand(&self.community_cards);
-            println!("Player {} hand rank: {:?}", i, hand_rank);
-            if hand_rank > best_hand {
-                best_hand = hand_rank;
-                winner = i;
+        for &i in eligible {
+            if matches!(self.players[i].status, PlayerStatus::Folded(_)) {
+                continue;
+            }
+            let hand_rank = self.players[i].best_hand(board);
+            println!("{} hand rank: {:?}", self.players[i].info, hand_rank);
+            match hand_rank.cmp(&best_hand) {
+                std::cmp::Ordering::Greater => {
+                    best_hand = hand_rank;
+                    winners = vec![i];
+                }
+                std::cmp::Ordering::Equal => winners.push(i),
+                std::cmp::Ordering::Less => {}
             }
         }
-        winner
+        winners
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::action::LegalActions;
+    use crate::card::parse_cards;
+    use crate::controller::{boxed_controllers, PassiveBot, RandomBot, ScriptedController};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type ActionOrder = Rc<RefCell<Vec<usize>>>;
+
+    // 记录行动顺序的控制器
+    struct Recorder(ActionOrder);
+
+    impl PlayerController for Recorder {
+        fn decide(&mut self, view: &TableView, legal: &LegalActions) -> Action {
+            self.0.borrow_mut().push(view.seat);
+            PassiveBot.decide(view, legal)
+        }
+    }
+
+    fn recorders(count: usize) -> (ActionOrder, Vec<Box<dyn PlayerController>>) {
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let controllers = boxed_controllers(count, |_| Recorder(order.clone()));
+        (order, controllers)
+    }
+
+    fn passive_bots(count: usize) -> Vec<Box<dyn PlayerController>> {
+        boxed_controllers(count, |_| PassiveBot)
+    }
 
     #[test]
     fn test_game_creation() {
@@ -179,9 +1046,982 @@ mod tests {
     fn test_play_round() {
         println!("hhh");
         let mut game = Game::new(4, 1000);
-        game.play_round();
+        game.play_round(&mut passive_bots(4));
         for player in game.players {
             player.show_hand()
         }
     }
+
+    #[test]
+    fn test_controller_decides() {
+        let mut game = Game::new(3, 1000);
+        let mut controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(ScriptedController::new(vec![Action::Fold])),
+            Box::new(PassiveBot),
+            Box::new(PassiveBot),
+        ];
+        game.start_hand();
+        game.play_betting_round(&mut controllers);
+        assert_eq!(game.players[0].status, PlayerStatus::Folded(0));
+        assert_eq!(game.players[1].chips, 980);
+        assert_eq!(game.players[2].chips, 980);
+        assert_eq!(game.advance(), Ok(HandPhase::Flop));
+        assert_eq!(game.pot, 40);
+    }
+
+    #[test]
+    fn test_illegal_action_folds() {
+        let mut game = Game::new(3, 1000);
+        let mut controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(ScriptedController::new(vec![Action::Check])),
+            Box::new(PassiveBot),
+            Box::new(PassiveBot),
+        ];
+        game.start_hand();
+        game.play_betting_round(&mut controllers);
+        assert_eq!(game.players[0].status, PlayerStatus::Folded(0));
+    }
+
+    #[test]
+    fn test_chips_move_to_pot() {
+        let mut game = Game::new(3, 1000);
+        let mut controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(ScriptedController::new(vec![Action::Raise(60)])),
+            Box::new(PassiveBot),
+            Box::new(PassiveBot),
+        ];
+        game.start_hand();
+        game.play_betting_round(&mut controllers);
+        game.advance().unwrap();
+        assert_eq!(game.pot, 180);
+        assert_eq!(
+            game.players.iter().map(|p| p.chips).collect::<Vec<u32>>(),
+            vec![940, 940, 940]
+        );
+        assert!(game.players.iter().all(|p| p.bet() == 0));
+    }
+
+    #[test]
+    fn test_chip_conservation() {
+        for seed in 0..20 {
+            let mut game = Game::new(4, 1000);
+            let mut controllers = boxed_controllers(4, |i| RandomBot::with_seed(seed * 4 + i));
+            game.play_round(&mut controllers);
+            assert_eq!(game.total_chips(), 4000);
+            assert_eq!(game.pot, 0);
+        }
+    }
+
+    #[test]
+    fn test_short_allin_wins_main_pot_only() {
+        let mut game = Game::new(3, 1000);
+        game.deal_to_players();
+        game.players[0].chips = 100;
+        game.players[0].hand = parse_cards("♠A;♥A").unwrap();
+        game.players[1].hand = parse_cards("♠K;♥K").unwrap();
+        game.players[2].hand = parse_cards("♠Q;♥Q").unwrap();
+        game.community_cards = parse_cards("♣2;♦7;♣9;♦J;♥3").unwrap();
+        game.players[0].commit(100);
+        game.players[1].commit(500);
+        game.players[2].commit(500);
+        game.showdown();
+        assert_eq!(game.players[0].chips, 300);
+        assert_eq!(game.players[1].chips, 500 + 800);
+        assert_eq!(game.players[2].chips, 500);
+        assert_eq!(game.pot, 0);
+        assert_eq!(game.total_chips(), 2100);
+    }
+
+    #[test]
+    fn test_split_pot_with_odd_chip() {
+        let mut game = Game::new(3, 1000);
+        game.deal_to_players();
+        game.button = 1;
+        game.players[0].hand = parse_cards("♠9;♥4").unwrap();
+        game.players[1].hand = parse_cards("♣9;♥5").unwrap();
+        game.players[2].hand = parse_cards("♠K;♥K").unwrap();
+        game.community_cards = parse_cards("♣A;♦K;♣Q;♦J;♥X").unwrap();
+        game.players[0].commit(101);
+        game.players[1].commit(101);
+        game.players[2].commit(99);
+        game.players[2].status = PlayerStatus::Folded(99);
+        let payouts = game.showdown();
+        // 0 和 1 都用公共牌的顺子，按钮为 1，按钮左手边第一位赢家为 0
+        assert_eq!(
+            payouts,
+            vec![
+                Payout {
+                    pot: 0,
+                    run: 0,
+                    player: 0,
+                    amount: 151
+                },
+                Payout {
+                    pot: 0,
+                    run: 0,
+                    player: 1,
+                    amount: 150
+                },
+            ]
+        );
+        assert_eq!(game.total_chips(), 3000);
+    }
+
+    #[test]
+    fn test_odd_chip_by_suit() {
+        let mut game = Game::new(3, 1000);
+        game.deal_to_players();
+        game.odd_chip_rule = OddChipRule::HighCardBySuit;
+        game.players[0].hand = parse_cards("♣9;♥4").unwrap();
+        game.players[1].hand = parse_cards("♠9;♥5").unwrap();
+        game.community_cards = parse_cards("♣A;♦K;♣Q;♦J;♥X").unwrap();
+        game.players[0].commit(50);
+        game.players[1].commit(50);
+        game.players[2].commit(1);
+        game.players[2].status = PlayerStatus::Folded(1);
+        let payouts = game.showdown();
+        // ♠9 大于 ♣9，零头给玩家1
+        assert_eq!(
+            payouts
+                .iter()
+                .map(|p| (p.player, p.amount))
+                .collect::<Vec<_>>(),
+            vec![(1, 51), (0, 50)]
+        );
+    }
+
+    #[test]
+    fn test_hand_ends_when_everyone_folds() {
+        let mut game = Game::new(3, 1000);
+        let mut controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(ScriptedController::new(vec![Action::Raise(60)])),
+            Box::new(ScriptedController::new(vec![Action::Fold])),
+            Box::new(ScriptedController::new(vec![Action::Fold])),
+        ];
+        let payouts = game.play_round(&mut controllers);
+        assert_eq!(
+            payouts,
+            vec![Payout {
+                pot: 0,
+                run: 0,
+                player: 0,
+                amount: 90
+            }]
+        );
+        assert!(game.community_cards.is_empty());
+        assert_eq!(
+            game.players.iter().map(|p| p.chips).collect::<Vec<u32>>(),
+            vec![1030, 990, 980]
+        );
+    }
+
+    #[test]
+    fn test_folded_players_cannot_win() {
+        let mut game = Game::new(2, 1000);
+        game.deal_to_players();
+        game.players[0].hand = parse_cards("♠A;♥A").unwrap();
+        game.players[1].hand = parse_cards("♠2;♥7").unwrap();
+        game.community_cards = parse_cards("♣A;♦K;♣5;♦J;♥3").unwrap();
+        game.players[0].status = PlayerStatus::Folded(0);
+        assert_eq!(game.determine_winners(&[0, 1]), vec![1]);
+    }
+
+    #[test]
+    fn test_blinds_and_action_order() {
+        let mut game = Game::new(4, 1000);
+        game.button = 3;
+        let (order, mut controllers) = recorders(4);
+        game.start_hand();
+        assert_eq!(game.small_blind_seat(), 0);
+        assert_eq!(game.big_blind_seat(), 1);
+        game.play_betting_round(&mut controllers);
+        assert_eq!(*order.borrow(), vec![2, 3, 0, 1]);
+        assert_eq!(game.players[0].chips, 980);
+        order.borrow_mut().clear();
+        game.advance().unwrap();
+        game.play_betting_round(&mut controllers);
+        assert_eq!(*order.borrow(), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_heads_up_blinds() {
+        let mut game = Game::new(2, 1000);
+        let (order, mut controllers) = recorders(2);
+        game.start_hand();
+        // 单挑时按钮下小盲并在翻牌前先行动，翻牌后后行动
+        assert_eq!(game.small_blind_seat(), 0);
+        assert_eq!(game.big_blind_seat(), 1);
+        game.play_betting_round(&mut controllers);
+        assert_eq!(*order.borrow(), vec![0, 1]);
+        order.borrow_mut().clear();
+        game.advance().unwrap();
+        game.play_betting_round(&mut controllers);
+        assert_eq!(*order.borrow(), vec![1, 0]);
+    }
+
+    #[test]
+    fn test_button_moves_each_hand() {
+        let mut game = Game::new(3, 1000);
+        game.play_round(&mut passive_bots(3));
+        assert_eq!(game.button, 1);
+        game.play_round(&mut passive_bots(3));
+        assert_eq!(game.button, 2);
+        game.play_round(&mut passive_bots(3));
+        assert_eq!(game.button, 0);
+    }
+
+    #[test]
+    fn test_hand_phases() {
+        let mut game = Game::new(3, 1000);
+        assert_eq!(game.phase, HandPhase::Complete);
+        game.start_hand();
+        assert_eq!(game.phase, HandPhase::Preflop);
+        assert_eq!(game.to_act(), Some(0));
+        assert_eq!(game.advance(), Err(GameError::BettingRoundOpen));
+        assert_eq!(
+            game.apply_action(1, Action::Call),
+            Err(GameError::NotYourTurn {
+                expected: 0,
+                player: 1
+            })
+        );
+        assert_eq!(
+            game.apply_action(0, Action::Check),
+            Err(GameError::InvalidAction(ActionError::CheckFacingBet {
+                to_call: 20
+            }))
+        );
+        game.apply_action(0, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        // 大盲仍可以选择加注或过牌
+        assert_eq!(game.to_act(), Some(2));
+        game.apply_action(2, Action::Check).unwrap();
+        assert_eq!(game.to_act(), None);
+
+        for (phase, cards) in [
+            (HandPhase::Flop, 3),
+            (HandPhase::Turn, 4),
+            (HandPhase::River, 5),
+        ] {
+            assert_eq!(game.advance(), Ok(phase));
+            assert_eq!(game.community_cards.len(), cards);
+            assert_eq!(game.pot, 60);
+            for player in [1, 2, 0] {
+                assert_eq!(game.to_act(), Some(player));
+                game.apply_action(player, Action::Check).unwrap();
+            }
+        }
+        assert_eq!(game.advance(), Ok(HandPhase::Showdown));
+        assert_eq!(game.advance(), Ok(HandPhase::Complete));
+        assert_eq!(game.payouts.iter().map(|p| p.amount).sum::<u32>(), 60);
+        assert_eq!(game.advance(), Err(GameError::NoHandInProgress));
+        assert_eq!(game.total_chips(), 3000);
+    }
+
+    #[test]
+    fn test_raise_reopens_action() {
+        let mut game = Game::new(3, 1000);
+        game.start_hand();
+        game.apply_action(0, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Raise(60)).unwrap();
+        assert_eq!(game.to_act(), Some(0));
+        game.apply_action(0, Action::Call).unwrap();
+        game.apply_action(1, Action::Fold).unwrap();
+        assert_eq!(game.to_act(), None);
+        assert_eq!(game.advance(), Ok(HandPhase::Flop));
+        assert_eq!(game.pot, 140);
+    }
+
+    #[test]
+    fn test_check_around() {
+        let mut game = Game::new(3, 1000);
+        game.start_hand();
+        for player in [0, 1] {
+            game.apply_action(player, Action::Call).unwrap();
+        }
+        game.apply_action(2, Action::Check).unwrap();
+        game.advance().unwrap();
+        for player in [1, 2, 0] {
+            assert_eq!(game.to_act(), Some(player));
+            game.apply_action(player, Action::Check).unwrap();
+        }
+        assert_eq!(game.to_act(), None);
+    }
+
+    #[test]
+    fn test_big_blind_option_raise() {
+        let mut game = Game::new(3, 1000);
+        game.start_hand();
+        game.apply_action(0, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(
+            game.legal_actions().unwrap().actions(),
+            vec![
+                Action::Fold,
+                Action::Check,
+                Action::Raise(40),
+                Action::AllIn
+            ]
+        );
+        game.apply_action(2, Action::Raise(80)).unwrap();
+        // 大盲加注后其他玩家重新行动
+        assert_eq!(game.to_act(), Some(0));
+        game.apply_action(0, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(game.to_act(), None);
+    }
+
+    #[test]
+    fn test_raise_and_reraise() {
+        let mut game = Game::new(3, 1000);
+        game.start_hand();
+        game.apply_action(0, Action::Raise(60)).unwrap();
+        assert_eq!(game.min_raise, 40);
+        game.apply_action(1, Action::Raise(200)).unwrap();
+        assert_eq!(game.min_raise, 140);
+        game.apply_action(2, Action::Fold).unwrap();
+        assert_eq!(game.to_act(), Some(0));
+        assert_eq!(
+            game.apply_action(0, Action::Raise(300)),
+            Err(GameError::InvalidAction(ActionError::BelowMinimum {
+                amount: 300,
+                minimum: 340
+            }))
+        );
+        game.apply_action(0, Action::Raise(340)).unwrap();
+        assert_eq!(game.to_act(), Some(1));
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(game.to_act(), None);
+        game.advance().unwrap();
+        assert_eq!(game.pot, 340 * 2 + 20);
+    }
+
+    #[test]
+    fn test_short_allin_does_not_reopen_raising() {
+        let mut game = Game::new(3, 1000);
+        game.players[1].chips = 150;
+        game.start_hand();
+        game.apply_action(0, Action::Raise(100)).unwrap();
+        // 小盲全下 150，只多了 50，不足一个完整加注 (80)
+        game.apply_action(1, Action::AllIn).unwrap();
+        assert_eq!(game.current_bet, 150);
+        assert_eq!(game.min_raise, 80);
+        // 大盲还没行动过，可以加注
+        assert_eq!(game.to_act(), Some(2));
+        assert!(game.legal_actions().unwrap().can_raise());
+        game.apply_action(2, Action::Call).unwrap();
+        // 加注者只能跟注或弃牌
+        assert_eq!(game.to_act(), Some(0));
+        assert_eq!(
+            game.legal_actions().unwrap().actions(),
+            vec![Action::Fold, Action::Call]
+        );
+        assert_eq!(
+            game.apply_action(0, Action::Raise(400)),
+            Err(GameError::InvalidAction(ActionError::RaiseClosed))
+        );
+        game.apply_action(0, Action::Call).unwrap();
+        assert_eq!(game.to_act(), None);
+    }
+
+    #[test]
+    fn test_cumulative_short_allins_reopen_raising() {
+        let mut game = Game::new(4, 1000);
+        game.players[0].chips = 150;
+        game.players[1].chips = 200;
+        game.start_hand();
+        game.apply_action(3, Action::Raise(100)).unwrap();
+        game.apply_action(0, Action::AllIn).unwrap();
+        game.apply_action(1, Action::AllIn).unwrap();
+        game.apply_action(2, Action::Call).unwrap();
+        // 两次不足额全下合计加注 100，达到一个完整加注
+        assert_eq!(game.to_act(), Some(3));
+        assert!(game.legal_actions().unwrap().can_raise());
+        game.apply_action(3, Action::Call).unwrap();
+        assert_eq!(game.to_act(), None);
+    }
+
+    #[test]
+    fn test_everyone_allin_runs_to_showdown() {
+        let mut game = Game::new(2, 1000);
+        game.start_hand();
+        game.apply_action(0, Action::AllIn).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(game.to_act(), None);
+        assert!(game.betting_closed());
+        // 没有人能再下注，亮牌后一次发完公共牌
+        assert_eq!(game.advance(), Ok(HandPhase::Showdown));
+        assert_eq!(game.community_cards.len(), 5);
+        assert!(game.exposed);
+        assert!(game.view(0).players[1].shown.is_some());
+        assert_eq!(game.advance(), Ok(HandPhase::Complete));
+        assert_eq!(game.total_chips(), 2000);
+    }
+
+    #[test]
+    fn test_view_and_history_round_trip() {
+        let mut game = Game::new(3, 1000);
+        game.play_round(&mut passive_bots(3));
+        let view = game.view(0);
+        let bytes = bincode::serialize(&view).unwrap();
+        let decoded: TableView = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.hand, view.hand);
+        assert_eq!(decoded.community_cards, game.community_cards);
+        let bytes = bincode::serialize(&game.history).unwrap();
+        assert_eq!(
+            bincode::deserialize::<HandHistory>(&bytes).unwrap(),
+            game.history
+        );
+    }
+
+    #[test]
+    fn test_stacked_set_over_set() {
+        let mut game = Game::new(2, 1000);
+        game.deck = Deck::with_hands(
+            &[
+                Some((Card::from("♠9"), Card::from("♥9"))),
+                Some((Card::from("♠8"), Card::from("♥8"))),
+            ],
+            &parse_cards("♣9;♦8;♣2;♦3;♠K").unwrap(),
+        )
+        .unwrap();
+        game.start_hand();
+        assert_eq!(game.players[0].hand, parse_cards("♠9;♥9").unwrap());
+        assert_eq!(game.players[1].hand, parse_cards("♠8;♥8").unwrap());
+        game.apply_action(0, Action::AllIn).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(game.advance(), Ok(HandPhase::Showdown));
+        assert_eq!(game.community_cards, parse_cards("♣9;♦8;♣2;♦3;♠K").unwrap());
+        assert_eq!(game.advance(), Ok(HandPhase::Complete));
+        assert_eq!(game.players[0].chips, 2000);
+        assert_eq!(game.players[1].chips, 0);
+    }
+
+    #[test]
+    fn test_allin_on_flop_runs_out() {
+        let mut game = Game::new(3, 1000);
+        game.players[2].chips = 200;
+        game.start_hand();
+        game.apply_action(0, Action::Call).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        game.apply_action(2, Action::Check).unwrap();
+        assert_eq!(game.advance(), Ok(HandPhase::Flop));
+        game.apply_action(1, Action::Bet(100)).unwrap();
+        game.apply_action(2, Action::AllIn).unwrap();
+        game.apply_action(0, Action::Fold).unwrap();
+        // 1 号面对全下还要跟注，之后没有人能再下注
+        assert_eq!(game.to_act(), Some(1));
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(game.advance(), Ok(HandPhase::Showdown));
+        assert_eq!(game.community_cards.len(), 5);
+        assert!(game.view(1).players[0].shown.is_none());
+    }
+
+    #[test]
+    fn test_run_it_twice() {
+        let mut game = Game::new(2, 1000);
+        game.runs = 2;
+        game.start_hand();
+        game.apply_action(0, Action::AllIn).unwrap();
+        game.apply_action(1, Action::Call).unwrap();
+        assert_eq!(game.advance(), Ok(HandPhase::Showdown));
+        let boards = game.boards();
+        assert_eq!(boards.len(), 2);
+        assert_ne!(boards[0], boards[1]);
+        assert_eq!(game.advance(), Ok(HandPhase::Complete));
+        for run in 0..2 {
+            let won: u32 = game
+                .payouts
+                .iter()
+                .filter(|payout| payout.run == run)
+                .map(|payout| payout.amount)
+                .sum();
+            assert_eq!(won, 1000);
+        }
+        assert_eq!(game.history.boards.len(), 2);
+        assert_eq!(game.total_chips(), 2000);
+    }
+
+    #[test]
+    fn test_run_it_twice_after_flop() {
+        let mut game = Game::new(2, 1000);
+        game.runs = 3;
+        game.start_hand();
+        game.apply_action(0, Action::Call).unwrap();
+        game.apply_action(1, Action::Check).unwrap();
+        game.advance().unwrap();
+        game.apply_action(1, Action::AllIn).unwrap();
+        game.apply_action(0, Action::Call).unwrap();
+        game.advance().unwrap();
+        // 三组公共牌共用翻牌，每组分到 2000 / 3，零头归第一组
+        let boards = game.boards();
+        assert_eq!(boards.len(), 3);
+        assert!(boards.iter().all(|board| board[..3] == boards[0][..3]));
+        game.advance().unwrap();
+        let first: u32 = game
+            .payouts
+            .iter()
+            .filter(|payout| payout.run == 0)
+            .map(|payout| payout.amount)
+            .sum();
+        assert_eq!(first, 668);
+        assert_eq!(game.total_chips(), 2000);
+    }
+
+    #[test]
+    fn test_pot_limit_game() {
+        let mut game = Game::new(3, 1000);
+        game.betting = BettingStructure::PotLimit;
+        game.start_hand();
+        assert_eq!(game.legal_actions().unwrap().raise_limit, 70);
+        game.apply_action(0, Action::Raise(70)).unwrap();
+        // 小盲跟注 60 后底池 160，最多加注到 230
+        assert_eq!(game.legal_actions().unwrap().raise_limit, 230);
+        assert!(game.apply_action(1, Action::Raise(240)).is_err());
+        game.apply_action(1, Action::Raise(230)).unwrap();
+    }
+
+    #[test]
+    fn test_fixed_limit_game() {
+        let mut game = Game::new(2, 1000);
+        game.betting = BettingStructure::FixedLimit {
+            small_bet: 20,
+            big_bet: 40,
+            raise_cap: 4,
+        };
+        game.start_hand();
+        // 大盲算第一注，之后最多再加注三次
+        for (player, to) in [(0, 40), (1, 60), (0, 80)] {
+            assert!(game.apply_action(player, Action::Raise(to + 10)).is_err());
+            game.apply_action(player, Action::Raise(to)).unwrap();
+        }
+        assert!(!game.legal_actions().unwrap().can_raise());
+        game.apply_action(1, Action::Call).unwrap();
+        game.advance().unwrap();
+        game.apply_action(1, Action::Check).unwrap();
+        game.apply_action(0, Action::Check).unwrap();
+        // 转牌开始用大注
+        game.advance().unwrap();
+        assert_eq!(
+            game.legal_actions().unwrap().actions(),
+            vec![Action::Fold, Action::Check, Action::Bet(40)]
+        );
+    }
+
+    #[test]
+    fn test_antes() {
+        let mut game = Game::new(3, 1000);
+        game.ante = Ante::PerPlayer(5);
+        game.start_hand();
+        assert_eq!(game.pot, 15);
+        assert_eq!(
+            game.players.iter().map(|p| p.chips).collect::<Vec<u32>>(),
+            vec![995, 985, 975]
+        );
+        // 前注是死筹码，不影响跟注金额
+        assert_eq!(game.legal_actions().unwrap().to_call(), 20);
+        assert_eq!(game.total_chips(), 3000);
+    }
+
+    #[test]
+    fn test_big_blind_ante() {
+        let mut game = Game::new(4, 1000);
+        game.ante = Ante::BigBlind(20);
+        game.start_hand();
+        assert_eq!(game.pot, 20);
+        assert_eq!(game.players[2].chips, 960);
+        assert_eq!(game.players[2].bet(), 20);
+        assert_eq!(game.total_chips(), 4000);
+    }
+
+    #[test]
+    fn test_straddle() {
+        let mut game = Game::new(4, 1000);
+        game.straddle = true;
+        let (order, mut controllers) = recorders(4);
+        game.start_hand();
+        assert_eq!(game.straddle_seat(), Some(3));
+        assert_eq!(game.current_bet, 40);
+        assert_eq!(game.min_raise, 40);
+        assert_eq!(game.to_act(), Some(0));
+        game.play_betting_round(&mut controllers);
+        // 抓位玩家翻牌前最后行动
+        assert_eq!(*order.borrow(), vec![0, 1, 2, 3]);
+        game.advance().unwrap();
+        assert_eq!(game.pot, 160);
+    }
+
+    #[test]
+    fn test_no_straddle_heads_up() {
+        let mut game = Game::new(2, 1000);
+        game.straddle = true;
+        game.start_hand();
+        assert_eq!(game.straddle_seat(), None);
+        assert_eq!(game.current_bet, 20);
+    }
+
+    #[test]
+    fn test_bomb_pot() {
+        let mut game = Game::new(3, 1000);
+        game.bomb_pot = Some(50);
+        game.start_hand();
+        assert_eq!(game.phase, HandPhase::Flop);
+        assert_eq!(game.community_cards.len(), 3);
+        assert_eq!(game.pot, 150);
+        assert_eq!(game.current_bet, 0);
+        assert_eq!(game.to_act(), Some(1));
+        assert_eq!(game.bomb_pot, None);
+        game.play_betting_round(&mut passive_bots(3));
+        assert_eq!(game.advance(), Ok(HandPhase::Turn));
+        assert_eq!(game.total_chips(), 3000);
+    }
+
+    #[test]
+    fn test_busted_players_sit_out() {
+        let mut game = Game::new(3, 1000);
+        game.players[1].chips = 0;
+        game.start_hand();
+        assert_eq!(game.players[1].status, PlayerStatus::Waiting);
+        // 剩下两人单挑，按钮下小盲
+        assert_eq!(game.small_blind_seat(), 0);
+        assert_eq!(game.big_blind_seat(), 2);
+        assert_eq!(game.deck.cards.len(), 52 - 4);
+        game.move_button();
+        assert_eq!(game.button, 2);
+    }
+
+    #[test]
+    fn test_join_and_leave() {
+        let mut game = Game::with_table_size(TableSize::SixMax);
+        assert_eq!(game.join(Some(3), PlayerInfo::new(1, "Alice"), 1000), Ok(0));
+        assert_eq!(game.join(None, PlayerInfo::new(2, "Bob"), 1000), Ok(0));
+        assert_eq!(game.join(Some(5), PlayerInfo::new(3, "Carol"), 1000), Ok(2));
+        assert_eq!(
+            game.join(Some(3), PlayerInfo::default(), 1000),
+            Err(SeatError::SeatTaken(3))
+        );
+        assert_eq!(
+            game.join(Some(6), PlayerInfo::default(), 1000),
+            Err(SeatError::NoSuchSeat(6))
+        );
+        let seats: Vec<(u32, &str)> = game
+            .players
+            .iter()
+            .map(|p| (p.position, p.info.name.as_str()))
+            .collect();
+        assert_eq!(seats, vec![(0, "Bob"), (3, "Alice"), (5, "Carol")]);
+        assert_eq!(game.free_seats(), vec![1, 2, 4]);
+        // 还没有打过牌，入座的玩家不用补大盲
+        assert_eq!(game.players[2].missed_blinds, MissedBlinds::None);
+
+        game.start_hand();
+        assert_eq!(
+            game.join(None, PlayerInfo::default(), 1000),
+            Err(SeatError::HandInProgress)
+        );
+        assert!(matches!(game.leave(0), Err(SeatError::HandInProgress)));
+        game.play_round(&mut passive_bots(3));
+        assert!(matches!(game.leave(1), Err(SeatError::EmptySeat(1))));
+        assert_eq!(game.leave(3).unwrap().position, 3);
+        for seat in [1, 2, 3, 4] {
+            let info = PlayerInfo::new(u64::from(seat) + 10, "Guest");
+            let index = game.join(Some(seat), info, 1000).unwrap();
+            // 打过牌之后入座的玩家要补大盲
+            assert_eq!(game.players[index].missed_blinds, MissedBlinds::BigBlind);
+        }
+        assert_eq!(
+            game.join(None, PlayerInfo::default(), 1000),
+            Err(SeatError::TableFull)
+        );
+    }
+
+    #[test]
+    fn test_first_hand_has_no_extra_blind() {
+        let mut game = Game::with_table_size(TableSize::SixMax);
+        for seat in 0..4 {
+            let info = PlayerInfo::new(u64::from(seat), "Guest");
+            game.join(Some(seat), info, 1000).unwrap();
+        }
+        game.start_hand();
+        assert_eq!(
+            game.players.iter().map(|p| p.bet()).collect::<Vec<u32>>(),
+            vec![0, 10, 20, 0]
+        );
+    }
+
+    #[test]
+    fn test_dead_button() {
+        let mut game = Game::new(4, 1000);
+        game.dead_button = true;
+        game.play_round(&mut passive_bots(4));
+        // 下一手的小盲离座: 大盲照常往前移一位，这一手没有小盲
+        game.leave(2).unwrap();
+        game.start_hand();
+        let bets: Vec<u32> = game.players.iter().map(|p| p.bet()).collect();
+        assert_eq!(bets, vec![0, 0, 20]);
+        assert_eq!(game.big_blind_seat(), 2);
+        assert_eq!(game.to_act(), Some(0));
+
+        // 移动按钮规则下盲注跟着按钮走
+        let mut game = Game::new(4, 1000);
+        game.play_round(&mut passive_bots(4));
+        game.leave(2).unwrap();
+        game.start_hand();
+        let bets: Vec<u32> = game.players.iter().map(|p| p.bet()).collect();
+        assert_eq!(bets, vec![20, 0, 10]);
+    }
+
+    #[test]
+    fn test_dead_button_after_big_blind_busts() {
+        let mut game = Game::new(4, 1000);
+        game.dead_button = true;
+        game.play_round(&mut passive_bots(4));
+        // 上一手的大盲输光，小盲空缺，按钮留在上一手小盲
+        game.players[2].chips = 0;
+        game.start_hand();
+        assert_eq!(game.players[2].status, PlayerStatus::Waiting);
+        let bets: Vec<u32> = game.players.iter().map(|p| p.bet()).collect();
+        assert_eq!(bets, vec![0, 0, 0, 20]);
+        assert_eq!(game.button, 1);
+        assert_eq!(game.to_act(), Some(0));
+    }
+
+    #[test]
+    fn test_next_big_blind_seat() {
+        // 每种情况下两手牌之间推算的大盲位和开始下一手后的大盲位一致
+        let check = |game: &mut Game| {
+            let predicted = game.next_big_blind_seat();
+            game.start_hand();
+            assert_eq!(predicted, Some(game.big_blind_seat()));
+        };
+        let mut game = Game::new(4, 1000);
+        game.play_round(&mut passive_bots(4));
+        check(&mut game);
+
+        let mut game = Game::new(2, 1000);
+        game.play_round(&mut passive_bots(2));
+        check(&mut game);
+
+        // 单挑时按钮上的玩家暂离
+        let mut game = Game::new(3, 1000);
+        game.play_round(&mut passive_bots(3));
+        game.players[game.button].sitting_out = true;
+        check(&mut game);
+
+        let mut game = Game::new(4, 1000);
+        game.dead_button = true;
+        game.play_round(&mut passive_bots(4));
+        game.players[2].chips = 0;
+        check(&mut game);
+        assert_eq!(game.big_blind_seat(), 3);
+    }
+
+    #[test]
+    fn test_new_player_posts_big_blind() {
+        let mut game = Game::new(4, 1000);
+        game.seats = TableSize::SixMax.seats();
+        game.play_round(&mut passive_bots(4));
+        assert_eq!(game.join(Some(4), PlayerInfo::default(), 1000), Ok(4));
+        game.start_hand();
+        assert_eq!(game.big_blind_seat(), 3);
+        assert_eq!(game.players[4].bet(), 20);
+        assert_eq!(game.players[4].missed_blinds, MissedBlinds::None);
+        assert_eq!(game.total_chips(), 5000);
+
+        let (order, mut controllers) = recorders(5);
+        game.play_betting_round(&mut controllers);
+        assert_eq!(*order.borrow(), vec![4, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_hand_history() {
+        let mut game = Game::new(3, 1000);
+        game.players[1].info = PlayerInfo::new(42, "Alice");
+        let mut controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(ScriptedController::new(vec![Action::Fold])),
+            Box::new(ScriptedController::new(vec![Action::Raise(60)])),
+            Box::new(ScriptedController::new(vec![Action::Fold])),
+        ];
+        game.play_round(&mut controllers);
+        let history = &game.history;
+        assert_eq!(history.seats.len(), 3);
+        assert_eq!(history.seats[1].player.name, "Alice");
+        let actions: Vec<(u64, Action)> = history
+            .actions
+            .iter()
+            .map(|record| (record.player, record.action))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (0, Action::Fold),
+                (42, Action::Raise(60)),
+                (2, Action::Fold)
+            ]
+        );
+        assert_eq!(
+            history.payouts,
+            vec![PayoutRecord {
+                pot: 0,
+                run: 0,
+                player: 42,
+                amount: 80
+            }]
+        );
+        let text = history.to_string();
+        assert!(text.contains("Seat 1: Alice (1000 chips)"));
+        assert!(text.contains("Preflop: Alice raise 60"));
+        assert!(text.contains("Pot 0: Alice wins 80"));
+    }
+
+    #[test]
+    fn test_sitting_out_owes_big_blind() {
+        let mut game = Game::new(5, 1000);
+        game.set_sitting_out(4, true).unwrap();
+        for _ in 0..3 {
+            game.play_round(&mut passive_bots(5));
+            assert_eq!(game.players[4].status, PlayerStatus::Waiting);
+        }
+        // 第三手大盲从 3 号座位跳到 0 号座位，越过了暂离的 4 号
+        assert_eq!(game.players[4].missed_blinds, MissedBlinds::BigBlind);
+        game.play_round(&mut passive_bots(5));
+        assert_eq!(game.players[4].missed_blinds, MissedBlinds::BigBlind);
+
+        game.set_sitting_out(4, false).unwrap();
+        game.start_hand();
+        assert_eq!(game.big_blind_seat(), 2);
+        assert_eq!(game.players[4].bet(), 20);
+        assert_eq!(game.players[4].missed_blinds, MissedBlinds::None);
+        assert_eq!(game.total_chips(), 5000);
+    }
+
+    #[test]
+    fn test_sitting_out_button_heads_up() {
+        let mut game = Game::new(3, 1000);
+        game.play_round(&mut passive_bots(3));
+        let button = game.button;
+        let chips = game.players[button].chips;
+        game.set_sitting_out(game.players[button].position, true)
+            .unwrap();
+        game.start_hand();
+        assert_eq!(game.players[button].status, PlayerStatus::Waiting);
+        assert!(game.players[button].hand.is_empty());
+        assert_ne!(game.button, button);
+        assert_eq!(game.small_blind_seat(), game.button);
+        assert_eq!(game.players[game.button].bet(), 10);
+        assert_eq!(game.players[game.big_blind_seat()].bet(), 20);
+        let mut controllers = passive_bots(3);
+        while game.phase != HandPhase::Complete {
+            game.play_betting_round(&mut controllers);
+            game.advance().unwrap();
+        }
+        assert_eq!(game.players[button].chips, chips);
+        assert_eq!(game.total_chips(), 3000);
+    }
+
+    #[test]
+    fn test_missed_both_blinds() {
+        let mut game = Game::new(4, 1000);
+        game.players[3].sitting_out = true;
+        game.players[3].missed_blinds = MissedBlinds::BigBlind;
+        game.mark_missed_blinds(2, 0);
+        assert_eq!(game.players[3].missed_blinds, MissedBlinds::Both);
+
+        // 大盲是活的，小盲作为死筹码进底池
+        game.players[3].sitting_out = false;
+        game.start_hand();
+        assert_eq!(game.players[3].bet(), 20);
+        assert_eq!(game.pot, 10);
+    }
+
+    // 每次决策都慢一点的控制器
+    struct SlowBot;
+
+    impl PlayerController for SlowBot {
+        fn decide(&mut self, view: &TableView, legal: &LegalActions) -> Action {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            PassiveBot.decide(view, legal)
+        }
+    }
+
+    #[test]
+    fn test_timeout_checks_or_folds() {
+        let mut game = Game::new(3, 1000);
+        let no_time = std::time::Duration::ZERO;
+        game.clock = Some(ActionClock::new(no_time, no_time, no_time));
+        let mut controllers: Vec<Box<dyn PlayerController>> =
+            vec![Box::new(SlowBot), Box::new(SlowBot), Box::new(SlowBot)];
+        let payouts = game.play_round(&mut controllers);
+        let actions: Vec<Action> = game
+            .history
+            .actions
+            .iter()
+            .map(|record| record.action)
+            .collect();
+        assert_eq!(actions, vec![Action::Fold, Action::Fold]);
+        assert_eq!(payouts[0].player, 2);
+    }
+
+    #[test]
+    fn test_pre_actions() {
+        let mut game = Game::new(4, 1000);
+        assert_eq!(
+            game.set_pre_action(1, Some(PreAction::CallAny)),
+            Err(GameError::NoHandInProgress)
+        );
+        game.start_hand();
+        game.set_pre_action(1, Some(PreAction::CallAny)).unwrap();
+        game.set_pre_action(2, Some(PreAction::Check)).unwrap();
+        game.set_pre_action(0, Some(PreAction::Call(20))).unwrap();
+        game.apply_action(3, Action::Raise(60)).unwrap();
+        assert_eq!(game.set_pre_action(3, Some(PreAction::CheckFold)), Ok(()));
+
+        // 0 号的跟注 20 和 2 号的过牌都因为加注作废，由玩家自己决定
+        let (order, mut controllers) = recorders(4);
+        game.play_betting_round(&mut controllers);
+        assert_eq!(*order.borrow(), vec![0, 2]);
+        let actions: Vec<(u64, Action)> = game
+            .history
+            .actions
+            .iter()
+            .map(|record| (record.player, record.action))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (3, Action::Raise(60)),
+                (0, Action::Call),
+                (1, Action::Call),
+                (2, Action::Call)
+            ]
+        );
+        // 新的一条街清空预选行动
+        game.advance().unwrap();
+        assert_eq!(game.players[3].pre_action, None);
+        game.apply_action(1, Action::Check).unwrap();
+        assert_eq!(
+            game.set_pre_action(0, Some(PreAction::FoldToAnyBet)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_seeded_hands_replay() {
+        let play = || {
+            let mut game = Game::new(3, 1000);
+            game.deck = Deck::with_seed(2024);
+            let mut controllers = boxed_controllers(3, RandomBot::with_seed);
+            (0..5)
+                .map(|_| {
+                    game.play_round(&mut controllers);
+                    game.history.clone()
+                })
+                .collect::<Vec<HandHistory>>()
+        };
+        let histories = play();
+        assert_eq!(histories, play());
+        assert!(histories.iter().all(|history| history.seed.is_some()));
+        assert!(histories[0].to_string().starts_with("Seed: "));
+    }
 }
@@ -1,6 +1,9 @@
-use crate::card::{parse_cards, Card};
+use crate::card::{parse_cards, Card, ParseCardError};
+use crate::card_set::CardSet;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum HandRank {
     HighCard(u8, u8, u8, u8, u8),
     OnePair(u8, u8, u8, u8),
@@ -15,9 +18,19 @@ pub enum HandRank {
 }
 
 impl HandRank {
-    pub fn from_cards_str(s: &str) -> Self {
-        let cards = parse_cards(s);
-        HandRank::from_cards(&cards)
+    pub fn from_cards_str(s: &str) -> Result<Self, ParseCardError> {
+        let cards = parse_cards(s)?;
+        Ok(HandRank::from_cards(&cards))
+    }
+
+    /// 从一组牌 (通常是手牌加公共牌) 中挑出最大的五张
+    pub fn best_of(cards: CardSet) -> Self {
+        cards
+            .iter()
+            .combinations(5)
+            .map(|combination| HandRank::from_cards(&combination))
+            .max()
+            .unwrap_or(HandRank::HighCard(0, 0, 0, 0, 0))
     }
 
     pub fn from_cards(cards: &[Card]) -> Self {
@@ -126,52 +139,51 @@ impl HandRank {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::card::Suit;
 
     #[test]
     fn test_card_type() {
         assert_eq!(
-            HandRank::from_cards_str("♥X;♥J;♥Q;♥K;♥A"),
+            HandRank::from_cards_str("♥X;♥J;♥Q;♥K;♥A").unwrap(),
             HandRank::RoyalFlush
         );
         assert_eq!(
-            HandRank::from_cards_str("♥9;♥X;♥J;♥Q;♥K"),
+            HandRank::from_cards_str("♥9;♥X;♥J;♥Q;♥K").unwrap(),
             HandRank::StraightFlush(13)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥9;♠9;♦9;♣9;♥K"),
+            HandRank::from_cards_str("♥9;♠9;♦9;♣9;♥K").unwrap(),
             HandRank::FourOfAKind(9, 13)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥9;♠9;♦9;♣K;♥K"),
+            HandRank::from_cards_str("♥9;♠9;♦9;♣K;♥K").unwrap(),
             HandRank::FullHouse(9, 13)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥2;♥5;♥7;♥9;♥K"),
+            HandRank::from_cards_str("♥2;♥5;♥7;♥9;♥K").unwrap(),
             HandRank::Flush(13, 9, 7, 5, 2)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥9;♥X;♠J;♥Q;♦K"),
+            HandRank::from_cards_str("♥9;♥X;♠J;♥Q;♦K").unwrap(),
             HandRank::Straight(13)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥2;♥A;♠3;♥4;♥5"),
+            HandRank::from_cards_str("♥2;♥A;♠3;♥4;♥5").unwrap(),
             HandRank::Straight(5)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥7;♠2;♦2;♣2;♥K"),
+            HandRank::from_cards_str("♥7;♠2;♦2;♣2;♥K").unwrap(),
             HandRank::ThreeOfAKind(2, 13, 7)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥7;♠2;♦2;♥7;♥K"),
+            HandRank::from_cards_str("♥7;♠2;♦2;♥7;♥K").unwrap(),
             HandRank::TwoPair(7, 2, 13)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥7;♠2;♦2;♥8;♥K"),
+            HandRank::from_cards_str("♥7;♠2;♦2;♥8;♥K").unwrap(),
             HandRank::OnePair(2, 13, 8, 7)
         );
         assert_eq!(
-            HandRank::from_cards_str("♥7;♠2;♦4;♥8;♦K"),
+            HandRank::from_cards_str("♥7;♠2;♦4;♥8;♦K").unwrap(),
             HandRank::HighCard(13, 8, 7, 4, 2)
         );
     }
@@ -209,7 +221,8 @@ mod tests {
         let mut i = 0;
         while i < ranks.len() - 1 {
             assert!(
-                HandRank::from_cards_str(ranks[i]) > HandRank::from_cards_str(&ranks[i + 1]),
+                HandRank::from_cards_str(ranks[i]).unwrap()
+                    > HandRank::from_cards_str(ranks[i + 1]).unwrap(),
                 "Assertion failed at index {}: {:?} is not greater than {:?}",
                 i,
                 ranks[i],
@@ -218,4 +231,18 @@ mod tests {
             i += 1;
         }
     }
+
+    #[test]
+    fn test_best_of() {
+        let cards: CardSet = parse_cards("Ah Kh 2c Qh Jh 7d Th")
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(HandRank::best_of(cards), HandRank::RoyalFlush);
+        let cards: CardSet = parse_cards("9s 9h 9c 8d 8s 2c 3d")
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(HandRank::best_of(cards), HandRank::FullHouse(9, 8));
+    }
 }
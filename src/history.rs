@@ -0,0 +1,112 @@
+use crate::action::Action;
+use crate::card::Card;
+use crate::game::HandPhase;
+use crate::player::PlayerInfo;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 一手牌开始时坐在桌上的玩家
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeatRecord {
+    pub seat: u32,
+    pub player: PlayerInfo,
+    pub chips: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActionRecord {
+    pub phase: HandPhase,
+    // 玩家 id
+    pub player: u64,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayoutRecord {
+    pub pot: usize,
+    pub run: usize,
+    // 玩家 id
+    pub player: u64,
+    pub amount: u32,
+}
+
+/// 一手牌的完整记录，玩家用 id 标识，不受座位和下标变化影响
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HandHistory {
+    // 这一手洗牌的种子，用 `Deck::shuffle_with` 可以还原牌序
+    pub seed: Option<u64>,
+    pub seats: Vec<SeatRecord>,
+    pub actions: Vec<ActionRecord>,
+    // 公共牌，全下后发多次时有多组
+    pub boards: Vec<Vec<Card>>,
+    pub payouts: Vec<PayoutRecord>,
+}
+
+impl HandHistory {
+    pub fn new(seed: Option<u64>, seats: Vec<SeatRecord>) -> Self {
+        Self {
+            seed,
+            seats,
+            ..Self::default()
+        }
+    }
+
+    /// 按 id 找玩家的名字，找不到时显示 id
+    pub fn name(&self, id: u64) -> String {
+        self.seats
+            .iter()
+            .find(|record| record.player.id == id)
+            .map(|record| record.player.name.clone())
+            .unwrap_or_else(|| format!("#{}", id))
+    }
+}
+
+impl fmt::Display for HandHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(seed) = self.seed {
+            writeln!(f, "Seed: {}", seed)?;
+        }
+        for record in &self.seats {
+            writeln!(
+                f,
+                "Seat {}: {} ({} chips)",
+                record.seat, record.player, record.chips
+            )?;
+        }
+        for record in &self.actions {
+            writeln!(
+                f,
+                "{:?}: {} {}",
+                record.phase,
+                self.name(record.player),
+                record.action
+            )?;
+        }
+        for (run, board) in self.boards.iter().enumerate() {
+            let cards = board
+                .iter()
+                .map(|card| card.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            match self.boards.len() {
+                1 => writeln!(f, "Board: [{}]", cards)?,
+                _ => writeln!(f, "Board {}: [{}]", run + 1, cards)?,
+            }
+        }
+        for record in &self.payouts {
+            let run = match self.boards.len() {
+                0 | 1 => String::new(),
+                _ => format!(" (run {})", record.run + 1),
+            };
+            writeln!(
+                f,
+                "Pot {}: {} wins {}{}",
+                record.pot,
+                self.name(record.player),
+                record.amount,
+                run
+            )?;
+        }
+        Ok(())
+    }
+}
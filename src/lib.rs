@@ -0,0 +1,17 @@
+pub mod action;
+pub mod betting;
+pub mod card;
+pub mod card_set;
+pub mod clock;
+pub mod command;
+pub mod controller;
+pub mod game;
+pub mod hand_rank;
+pub mod history;
+pub mod multi_table;
+pub mod player;
+pub mod pot;
+pub mod seat;
+pub mod session;
+pub mod tournament;
+pub mod utils;
@@ -1,22 +1,26 @@
-mod card;
-mod game;
-mod hand_rank;
-mod player;
-mod utils;
-
-use game::Game;
+use pocker::controller::{PassiveBot, PlayerController, TerminalController};
+use pocker::game::Game;
+use pocker::session::Session;
 
 struct Config {
     small_blind: u32,
     initial_chips: u32,
+    max_hands: u32,
 }
 
 const CONFIG: Config = Config {
     small_blind: 10,
     initial_chips: 1000,
+    max_hands: 100,
 };
 
 fn main() {
     let mut game = Game::new(2, CONFIG.initial_chips);
-    game.play_round();
+    game.small_blind = CONFIG.small_blind;
+    game.big_blind = CONFIG.small_blind * 2;
+    let controllers: Vec<Box<dyn PlayerController>> =
+        vec![Box::new(TerminalController), Box::new(PassiveBot)];
+    let mut session = Session::new(game, controllers);
+    session.max_hands = Some(CONFIG.max_hands);
+    session.run();
 }
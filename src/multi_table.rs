@@ -0,0 +1,371 @@
+use crate::controller::PlayerController;
+use crate::game::Game;
+use crate::player::{Player, PlayerInfo};
+use crate::tournament::{BlindLevel, BlindSchedule, Finish, PrizeStructure};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+/// 多桌赛中的一张牌桌，`entrants[i]` 是第 i 位玩家的参赛编号
+pub struct Table {
+    pub game: Game,
+    pub entrants: Vec<usize>,
+    controllers: Vec<Box<dyn PlayerController>>,
+}
+
+impl Table {
+    fn new(game: Game) -> Self {
+        Self {
+            game,
+            entrants: Vec::new(),
+            controllers: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entrants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entrants.is_empty()
+    }
+
+    fn seat(&mut self, entrant: usize, player: Player, controller: Box<dyn PlayerController>) {
+        let index = self.game.sit(None, player).unwrap();
+        self.entrants.insert(index, entrant);
+        self.controllers.insert(index, controller);
+    }
+
+    fn unseat(&mut self, index: usize) -> (usize, Player, Box<dyn PlayerController>) {
+        let seat = self.game.players[index].position;
+        let player = self.game.leave(seat).unwrap();
+        let entrant = self.entrants.remove(index);
+        let controller = self.controllers.remove(index);
+        (entrant, player, controller)
+    }
+
+    /// 下一手牌的大盲位，换桌时优先移走这位玩家
+    fn next_big_blind(&self) -> usize {
+        self.game.next_big_blind_seat().unwrap_or(0)
+    }
+}
+
+/// 多桌锦标赛，统一升盲、淘汰、平衡牌桌，最后合并成决赛桌
+pub struct MultiTableTournament {
+    pub tables: Vec<Table>,
+    pub schedule: BlindSchedule,
+    pub prizes: PrizeStructure,
+    pub buy_in: u32,
+    pub table_size: usize,
+    pub rounds_played: u32,
+    entrant_count: usize,
+    // 按出局顺序记录的参赛编号
+    eliminated: Vec<usize>,
+}
+
+impl MultiTableTournament {
+    /*
+     * 用种子随机抽座位，同一个种子抽出的座位相同
+     * 玩家轮流分到各张桌，每桌人数最多相差一人
+     */
+    pub fn new(
+        controllers: Vec<Box<dyn PlayerController>>,
+        table_size: usize,
+        starting_stack: u32,
+        levels: Vec<BlindLevel>,
+        buy_in: u32,
+        seed: u64,
+    ) -> Self {
+        assert!(table_size >= 2, "a table needs at least two seats");
+        let entrant_count = controllers.len();
+        let schedule = BlindSchedule::new(levels);
+        let table_count = entrant_count.div_ceil(table_size).max(1);
+        let mut tables: Vec<Table> = (0..table_count)
+            .map(|_| {
+                let mut game = Game::new(0, starting_stack);
+                game.seats = table_size as u32;
+                schedule.current().apply(&mut game);
+                Table::new(game)
+            })
+            .collect();
+
+        let mut draw: Vec<usize> = (0..entrant_count).collect();
+        draw.shuffle(&mut StdRng::seed_from_u64(seed));
+        let mut controllers: Vec<Option<Box<dyn PlayerController>>> =
+            controllers.into_iter().map(Some).collect();
+        for (i, &entrant) in draw.iter().enumerate() {
+            let controller = controllers[entrant].take().unwrap();
+            let mut player = Player::new(starting_stack);
+            player.info = PlayerInfo::new(entrant as u64, &format!("Player {}", entrant));
+            tables[i % table_count].seat(entrant, player, controller);
+        }
+
+        Self {
+            tables,
+            schedule,
+            prizes: PrizeStructure::sit_and_go(entrant_count),
+            buy_in,
+            table_size,
+            rounds_played: 0,
+            entrant_count,
+            eliminated: Vec::new(),
+        }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.tables.iter().map(Table::len).sum()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.remaining() < 2
+    }
+
+    /// 每张桌各打一手牌，然后淘汰输光的玩家并平衡牌桌
+    pub fn play_round(&mut self) {
+        if self.schedule.update(self.rounds_played) {
+            let level = *self.schedule.current();
+            println!(
+                "Blinds up: {}/{} ante {}",
+                level.small_blind, level.big_blind, level.ante
+            );
+            for table in &mut self.tables {
+                level.apply(&mut table.game);
+            }
+        }
+        let stacks = self.stacks();
+        for table in &mut self.tables {
+            if table.game.funded_players() >= 2 {
+                table.game.play_round(&mut table.controllers);
+            }
+        }
+        self.rounds_played += 1;
+        self.eliminate(&stacks);
+        self.break_tables();
+        self.balance();
+    }
+
+    /*
+     * 移走输光的玩家，stacks 为这一轮开始时各桌玩家的筹码
+     * 同一轮出局的玩家，这一轮开始时筹码多的名次靠前
+     */
+    fn eliminate(&mut self, stacks: &[Vec<u32>]) {
+        let mut busted = Vec::new();
+        for (table, stacks) in self.tables.iter_mut().zip(stacks) {
+            for index in (0..table.len()).rev() {
+                if table.game.players[index].chips == 0 {
+                    let (entrant, player, _) = table.unseat(index);
+                    busted.push((stacks[index], entrant, player));
+                }
+            }
+        }
+        busted.sort_by_key(|&(stack, _, _)| stack);
+        for (_, entrant, player) in busted {
+            println!("{} is eliminated", player.info);
+            self.eliminated.push(entrant);
+        }
+    }
+
+    /// 各桌玩家当前的筹码，按下标排列
+    fn stacks(&self) -> Vec<Vec<u32>> {
+        self.tables
+            .iter()
+            .map(|table| table.game.players.iter().map(|p| p.chips).collect())
+            .collect()
+    }
+
+    /// 剩下的玩家坐得下更少的桌时，拆掉人最少的桌，玩家分到其他桌人最少的空位
+    fn break_tables(&mut self) {
+        while self.tables.len() > 1 && self.remaining() <= (self.tables.len() - 1) * self.table_size
+        {
+            let smallest = (0..self.tables.len())
+                .min_by_key(|&i| self.tables[i].len())
+                .unwrap();
+            let mut broken = self.tables.remove(smallest);
+            println!("Table {} is broken", smallest);
+            while !broken.is_empty() {
+                let (entrant, player, controller) = broken.unseat(0);
+                self.shortest_table().seat(entrant, player, controller);
+            }
+        }
+    }
+
+    /// 人数最多和最少的桌相差超过一人时，从多的桌移走下一手的大盲
+    fn balance(&mut self) {
+        loop {
+            let (Some(longest), Some(shortest)) = (
+                (0..self.tables.len()).max_by_key(|&i| self.tables[i].len()),
+                (0..self.tables.len()).min_by_key(|&i| self.tables[i].len()),
+            ) else {
+                return;
+            };
+            if self.tables[longest].len() <= self.tables[shortest].len() + 1 {
+                return;
+            }
+            let index = self.tables[longest].next_big_blind();
+            let (entrant, player, controller) = self.tables[longest].unseat(index);
+            println!("{} moves to table {}", player.info, shortest);
+            self.tables[shortest].seat(entrant, player, controller);
+        }
+    }
+
+    fn shortest_table(&mut self) -> &mut Table {
+        self.tables
+            .iter_mut()
+            .min_by_key(|table| table.len())
+            .unwrap()
+    }
+
+    pub fn run(&mut self) -> Vec<Finish> {
+        while !self.is_over() {
+            self.play_round();
+        }
+        let finishes = self.finishes();
+        for finish in &finishes {
+            println!(
+                "{}. Player {} wins {}",
+                finish.place, finish.player, finish.prize
+            );
+        }
+        finishes
+    }
+
+    /// 还在场的玩家按筹码排名，已出局的玩家越晚出局名次越靠前
+    pub fn finishes(&self) -> Vec<Finish> {
+        let mut alive: Vec<(usize, u32)> = self
+            .tables
+            .iter()
+            .flat_map(|table| {
+                table
+                    .entrants
+                    .iter()
+                    .zip(&table.game.players)
+                    .map(|(&entrant, player)| (entrant, player.chips))
+            })
+            .collect();
+        alive.sort_by_key(|&(_, chips)| std::cmp::Reverse(chips));
+        let payouts = self.prizes.payouts(self.buy_in * self.entrant_count as u32);
+        alive
+            .into_iter()
+            .map(|(entrant, _)| entrant)
+            .chain(self.eliminated.iter().rev().copied())
+            .enumerate()
+            .map(|(i, player)| Finish {
+                place: i + 1,
+                player,
+                prize: payouts.get(i).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+    use crate::card::{parse_cards, Card, Deck};
+    use crate::controller::{boxed_controllers, RandomBot, ScriptedController};
+    use crate::tournament::LevelDuration;
+
+    fn bots(count: usize) -> Vec<Box<dyn PlayerController>> {
+        boxed_controllers(count, RandomBot::with_seed)
+    }
+
+    fn levels() -> Vec<BlindLevel> {
+        vec![
+            BlindLevel::new(10, 20, 0, LevelDuration::Hands(3)),
+            BlindLevel::new(25, 50, 5, LevelDuration::Hands(3)),
+            BlindLevel::new(50, 100, 10, LevelDuration::Hands(3)),
+        ]
+    }
+
+    fn seating(tournament: &MultiTableTournament) -> Vec<Vec<usize>> {
+        tournament
+            .tables
+            .iter()
+            .map(|table| table.entrants.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_seat_draw() {
+        let tournament = MultiTableTournament::new(bots(10), 4, 1000, levels(), 10, 42);
+        let sizes: Vec<usize> = tournament.tables.iter().map(Table::len).collect();
+        assert_eq!(sizes, vec![4, 3, 3]);
+        let again = MultiTableTournament::new(bots(10), 4, 1000, levels(), 10, 42);
+        assert_eq!(seating(&tournament), seating(&again));
+        let other = MultiTableTournament::new(bots(10), 4, 1000, levels(), 10, 7);
+        assert_ne!(seating(&tournament), seating(&other));
+    }
+
+    #[test]
+    fn test_break_and_balance() {
+        let mut tournament = MultiTableTournament::new(bots(9), 3, 1000, levels(), 10, 1);
+        // 第一张桌只剩一人，从人多的桌移一人过来
+        tournament.tables[0].game.players[0].chips = 0;
+        tournament.tables[0].game.players[1].chips = 0;
+        tournament.eliminate(&tournament.stacks());
+        tournament.break_tables();
+        tournament.balance();
+        assert_eq!(tournament.tables.len(), 3);
+        let sizes: Vec<usize> = tournament.tables.iter().map(Table::len).collect();
+        assert_eq!(sizes, vec![2, 3, 2]);
+
+        // 剩下六人坐得下两张桌，拆掉人最少的桌
+        tournament.tables[1].game.players[0].chips = 0;
+        tournament.eliminate(&tournament.stacks());
+        tournament.break_tables();
+        tournament.balance();
+        let sizes: Vec<usize> = tournament.tables.iter().map(Table::len).collect();
+        assert_eq!(sizes, vec![3, 3]);
+    }
+
+    #[test]
+    fn test_balance_moves_one_player() {
+        let mut tournament = MultiTableTournament::new(bots(8), 4, 1000, levels(), 10, 3);
+        tournament.tables[0].game.players[0].chips = 0;
+        tournament.tables[0].game.players[1].chips = 0;
+        tournament.eliminate(&tournament.stacks());
+        tournament.break_tables();
+        tournament.balance();
+        let sizes: Vec<usize> = tournament.tables.iter().map(Table::len).collect();
+        assert_eq!(sizes, vec![3, 3]);
+    }
+
+    #[test]
+    fn test_same_round_busts_ranked_by_stack() {
+        let controllers = boxed_controllers(3, |_| ScriptedController::new(vec![Action::AllIn]));
+        let mut tournament = MultiTableTournament::new(controllers, 3, 1000, levels(), 10, 9);
+        let table = &mut tournament.tables[0];
+        table.game.players[1].chips = 300;
+        table.game.players[2].chips = 200;
+        table.game.deck = Deck::with_hands(
+            &[
+                Some((Card::from("As"), Card::from("Ah"))),
+                Some((Card::from("Ks"), Card::from("Kh"))),
+                Some((Card::from("Qs"), Card::from("Qh"))),
+            ],
+            &parse_cards("2c 3d 7h 8s Jd").unwrap(),
+        )
+        .unwrap();
+        let entrants = table.entrants.clone();
+        tournament.play_round();
+        let order: Vec<usize> = tournament
+            .finishes()
+            .iter()
+            .map(|finish| finish.player)
+            .collect();
+        assert_eq!(order, vec![entrants[0], entrants[1], entrants[2]]);
+    }
+
+    #[test]
+    fn test_runs_to_final_table() {
+        let mut tournament = MultiTableTournament::new(bots(12), 4, 300, levels(), 10, 5);
+        let finishes = tournament.run();
+        assert_eq!(tournament.tables.len(), 1);
+        assert_eq!(tournament.tables[0].game.total_chips(), 3600);
+        let mut players: Vec<usize> = finishes.iter().map(|finish| finish.player).collect();
+        players.sort();
+        assert_eq!(players, (0..12).collect::<Vec<usize>>());
+        assert_eq!(finishes[0].prize, 60);
+    }
+}
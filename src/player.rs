@@ -1,10 +1,37 @@
+use crate::action::{Action, ActionError, LegalActions, PreAction};
 use crate::card::Card;
 use crate::hand_rank::HandRank;
-use crate::utils::read_command;
-use itertools::Itertools;
-use std::cmp::Ordering::{Equal, Greater, Less};
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Debug)]
+/// 玩家身份，id 在换桌、离座后保持不变
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    pub id: u64,
+    pub name: String,
+    pub avatar: Option<String>,
+    // 国家或地区代码，例如 "CN"
+    pub country: Option<String>,
+}
+
+impl PlayerInfo {
+    pub fn new(id: u64, name: &str) -> Self {
+        Self {
+            id,
+            name: name.to_string(),
+            avatar: None,
+            country: None,
+        }
+    }
+}
+
+impl fmt::Display for PlayerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PlayerStatus {
     Folded(u32),
     Allin(u32),
@@ -12,90 +39,152 @@ pub enum PlayerStatus {
     Waiting,
 }
 
+/// 没有参与时错过的盲注，回到牌局时补上
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissedBlinds {
+    #[default]
+    None,
+    // 补一个活的大盲
+    BigBlind,
+    // 补一个活的大盲，小盲作为死筹码进底池
+    Both,
+}
+
 #[derive(Debug)]
 pub struct Player {
+    pub info: PlayerInfo,
     pub hand: Vec<Card>,
     pub chips: u32,
     pub status: PlayerStatus,
+    // 座位号
     pub position: u32,
+    // 本手牌累计投入的筹码，用于计算边池
+    pub total_bet: u32,
+    pub missed_blinds: MissedBlinds,
+    // 暂时离开，保留座位和筹码但不发牌
+    pub sitting_out: bool,
+    // 本轮预先选好的行动
+    pub pre_action: Option<PreAction>,
 }
 
 impl Player {
     pub fn new(chips: u32) -> Self {
         Self {
+            info: PlayerInfo::default(),
             hand: Vec::with_capacity(2),
             chips,
             status: PlayerStatus::Waiting,
             position: 0,
+            total_bet: 0,
+            missed_blinds: MissedBlinds::None,
+            sitting_out: false,
+            pre_action: None,
         }
     }
 
+    /// 有筹码且没有暂离，下一手可以发牌
+    pub fn can_play(&self) -> bool {
+        self.chips > 0 && !self.sitting_out
+    }
+
     pub fn receive_card(&mut self, hand_card: (Card, Card)) {
         self.hand = vec![hand_card.0, hand_card.1];
         self.status = PlayerStatus::Betting(0);
+        self.total_bet = 0;
+    }
+
+    /*
+     * 下注阶段，玩家可选的行动
+     * @param current_bet: u32 本轮最高下注，继续当前游戏需要跟到的筹码数
+     * @param min_raise: u32 最小加注幅度
+     */
+    pub fn legal_actions(&self, current_bet: u32, min_raise: u32) -> LegalActions {
+        match self.status {
+            PlayerStatus::Betting(s) => LegalActions::new(self.chips, s, current_bet, min_raise),
+            _ => panic!("Player is not gaming"),
+        }
+    }
+
+    /*
+     * 下注阶段，校验并执行玩家行动
+     * 返回玩家本轮的下注总额
+     */
+    pub fn place_bet(&mut self, action: Action, legal: &LegalActions) -> Result<u32, ActionError> {
+        let current_bet = legal.player_bet;
+        match legal.validate(action)? {
+            Action::Fold => self.status = PlayerStatus::Folded(current_bet),
+            Action::Check => {}
+            Action::Call => self.commit(legal.to_call()),
+            Action::Bet(amount) | Action::Raise(amount) => self.commit(amount - current_bet),
+            Action::AllIn => self.commit(self.chips),
+        }
+        Ok(self.bet())
     }
 
     /*
-     * 下注阶段，玩家决策
-     * @param mini_bet: u32 继续当前游戏需要下注的最小筹码数
+     * 从筹码中拿出 amount 加到本轮下注，筹码用完即为全下
+     * 盲注筹码不足时同样全下
      */
-    pub fn place_bet(&mut self, mini_bet: u32) -> u32 {
-        let current_bet = match self.status {
-            PlayerStatus::Betting(s) => Some(s),
-            _ => None,
+    pub fn commit(&mut self, amount: u32) {
+        let amount = amount.min(self.chips);
+        self.chips -= amount;
+        self.total_bet += amount;
+        let bet = self.bet() + amount;
+        self.status = if self.chips == 0 {
+            PlayerStatus::Allin(bet)
+        } else {
+            PlayerStatus::Betting(bet)
         };
+    }
+
+    /// 下前注，前注直接进底池，不算本轮下注，返回实际下的筹码数
+    pub fn post_ante(&mut self, amount: u32) -> u32 {
+        let amount = amount.min(self.chips);
+        self.chips -= amount;
+        self.total_bet += amount;
+        if self.chips == 0 {
+            self.status = PlayerStatus::Allin(self.bet());
+        }
+        amount
+    }
+
+    /// 本轮结束，收走玩家的下注，返回收走的筹码数
+    pub fn collect_bet(&mut self) -> u32 {
+        let bet = self.bet();
+        self.status = match self.status {
+            PlayerStatus::Folded(_) => PlayerStatus::Folded(0),
+            PlayerStatus::Allin(_) => PlayerStatus::Allin(0),
+            PlayerStatus::Betting(_) => PlayerStatus::Betting(0),
+            PlayerStatus::Waiting => PlayerStatus::Waiting,
+        };
+        bet
+    }
 
-        let current_bet = current_bet.expect("Player is not gaming");
-        // fold check call raise allin
-        let self_chips = self.chips;
-        let mut available_actions = vec!["fold"]; // 总是可以选择弃牌
-        match current_bet.cmp(&mini_bet) {
-            Equal => {
-                // 如果当前下注等于上一轮的下注，可以选择检查（Check）
-                available_actions.push("check");
-                // 如果有筹码可以选择加注或全下
-                if self_chips > 0 {
-                    available_actions.push("raise");
-                    available_actions.push("allin");
-                }
-            }
-            Less => {
-                // 如果当前下注小于上一轮的下注，说明上家加注了
-                if self_chips > mini_bet - current_bet {
-                    // 如果筹码足够，则可以跟注或加注
-                    available_actions.push("call");
-                    available_actions.push("raise");
-                }
-                // 不论筹码数，总可以选择全压
-                available_actions.push("allin");
-            }
-            Greater => unreachable!(),
+    /// 玩家本轮的下注额
+    pub fn bet(&self) -> u32 {
+        match self.status {
+            PlayerStatus::Folded(s) | PlayerStatus::Allin(s) | PlayerStatus::Betting(s) => s,
+            PlayerStatus::Waiting => 0,
         }
-        //TODO:获取玩家输入 返回下注金额
-        self.status = PlayerStatus::Betting(mini_bet);
-        mini_bet
     }
 
+    /// 新的一手牌开始前收回手牌并清空下注
     pub fn reset_bet(&mut self) {
+        self.hand.clear();
         self.status = PlayerStatus::Waiting;
+        self.pre_action = None;
+        self.total_bet = 0;
     }
 
     pub fn show_hand(&self) {
-        println!("{}{}, {:?}", self.hand[0], self.hand[1], self.status);
+        println!(
+            "{}: {}{}, {:?}",
+            self.info, self.hand[0], self.hand[1], self.status
+        );
     }
 
     pub fn best_hand(&self, community_cards: &[Card]) -> HandRank {
-        let mut all_cards = self.hand.clone();
-        all_cards.extend_from_slice(community_cards);
-        let mut best_rank = HandRank::HighCard(0, 0, 0, 0, 0);
-        for combination in all_cards.iter().combinations(5) {
-            let rank =
-                HandRank::from_cards(&combination.iter().map(|&&card| card).collect::<Vec<Card>>());
-            if rank > best_rank {
-                best_rank = rank;
-            }
-        }
-        best_rank
+        HandRank::best_of(self.hand.iter().chain(community_cards).copied().collect())
     }
 }
 
@@ -121,27 +210,75 @@ mod tests {
         assert_eq!(best_hand, HandRank::RoyalFlush);
     }
 
+    #[test]
+    fn test_legal_actions() {
+        let mut player = Player::new(100);
+        player.receive_card((Card::new(2, Suit::Clubs), Card::new(11, Suit::Hearts)));
+        assert_eq!(
+            player.legal_actions(0, 20).actions(),
+            vec![Action::Fold, Action::Check, Action::Bet(20), Action::AllIn]
+        );
+        assert_eq!(
+            player.legal_actions(20, 20).actions(),
+            vec![Action::Fold, Action::Call, Action::Raise(40), Action::AllIn]
+        );
+        assert_eq!(
+            player.legal_actions(200, 20).actions(),
+            vec![Action::Fold, Action::AllIn]
+        );
+    }
+
     #[test]
     fn test_bet() {
-        // let mut player = Player::new(1000);
-        // player.place_bet(200);
-        // let current_bet = match player.status {
-        //     PlayerStatus::Betting(s) => Some(s),
-        //     _ => None,
-        // };
-
-        // let current_bet = current_bet.expect("Player is not gaming");
-
-        // assert_eq!(player.chips, 800);
-        // assert_eq!(current_bet, 200);
-        // player.reset_bet();
-        // assert_eq!(player.chips, 800);
-        // let current_bet = match player.status {
-        //     PlayerStatus::Betting(s) => Some(s),
-        //     _ => None,
-        // };
-
-        // let current_bet = current_bet.expect("Player is not gaming");
-        // assert_eq!(current_bet, 0);
+        let mut player = Player::new(1000);
+        player.receive_card((Card::new(2, Suit::Clubs), Card::new(11, Suit::Hearts)));
+        let legal = player.legal_actions(20, 20);
+        assert_eq!(player.place_bet(Action::Call, &legal), Ok(20));
+        assert_eq!(player.status, PlayerStatus::Betting(20));
+        assert_eq!(player.chips, 980);
+        let legal = player.legal_actions(20, 20);
+        assert_eq!(
+            player.place_bet(Action::Raise(30), &legal),
+            Err(ActionError::BelowMinimum {
+                amount: 30,
+                minimum: 40
+            })
+        );
+        assert_eq!(player.place_bet(Action::Raise(60), &legal), Ok(60));
+        assert_eq!(player.status, PlayerStatus::Betting(60));
+        assert_eq!(player.chips, 940);
+        let legal = player.legal_actions(120, 60);
+        assert_eq!(player.place_bet(Action::Fold, &legal), Ok(60));
+        assert_eq!(player.status, PlayerStatus::Folded(60));
+        assert_eq!(player.chips, 940);
+        assert_eq!(player.collect_bet(), 60);
+        assert_eq!(player.status, PlayerStatus::Folded(0));
+        assert_eq!(player.total_bet, 60);
+    }
+
+    #[test]
+    fn test_post_ante() {
+        let mut player = Player::new(25);
+        player.receive_card((Card::new(2, Suit::Clubs), Card::new(11, Suit::Hearts)));
+        assert_eq!(player.post_ante(10), 10);
+        assert_eq!(player.status, PlayerStatus::Betting(0));
+        assert_eq!(player.post_ante(20), 15);
+        assert_eq!(player.status, PlayerStatus::Allin(0));
+        assert_eq!(player.total_bet, 25);
+    }
+
+    #[test]
+    fn test_commit_allin() {
+        let mut player = Player::new(15);
+        player.receive_card((Card::new(2, Suit::Clubs), Card::new(11, Suit::Hearts)));
+        player.commit(20);
+        assert_eq!(player.status, PlayerStatus::Allin(15));
+        assert_eq!(player.chips, 0);
+        let mut player = Player::new(1000);
+        player.receive_card((Card::new(2, Suit::Clubs), Card::new(11, Suit::Hearts)));
+        let legal = player.legal_actions(20, 20);
+        assert_eq!(player.place_bet(Action::AllIn, &legal), Ok(1000));
+        assert_eq!(player.status, PlayerStatus::Allin(1000));
+        assert_eq!(player.chips, 0);
     }
 }
@@ -0,0 +1,190 @@
+/// 一个底池以及有资格赢得它的玩家
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pot {
+    pub amount: u32,
+    pub eligible: Vec<usize>,
+}
+
+/// 平分底池时除不尽的筹码归属规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OddChipRule {
+    // 给按钮左手边的第一位赢家
+    #[default]
+    LeftOfButton,
+    // 给手牌中最大单张（同点数比花色）的赢家
+    HighCardBySuit,
+}
+
+/// 一位玩家从某个底池赢得的筹码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Payout {
+    pub pot: usize,
+    // 发多次公共牌时是第几次，只发一次时为 0
+    pub run: usize,
+    pub player: usize,
+    pub amount: u32,
+}
+
+/*
+ * 在赢家之间平分底池
+ * @param winners: &[usize] 已按零头优先级排好序的赢家
+ * 除不尽的筹码从第一位赢家开始每人一个
+ */
+pub fn split_pot(pot: usize, amount: u32, winners: &[usize]) -> Vec<Payout> {
+    let count = winners.len() as u32;
+    let share = amount / count;
+    let odd_chips = (amount % count) as usize;
+    winners
+        .iter()
+        .enumerate()
+        .map(|(i, &player)| Payout {
+            pot,
+            run: 0,
+            player,
+            amount: share + u32::from(i < odd_chips),
+        })
+        .collect()
+}
+
+/*
+ * 按整手牌的投入拆分主池和边池
+ * @param contributions: &[(u32, bool)] 每位玩家本手牌投入的筹码以及是否已弃牌
+ * 弃牌玩家的筹码留在底池里，但没有资格赢得底池
+ * 返回的第一个为主池，之后按全下金额从小到大依次为边池
+ */
+pub fn build_pots(contributions: &[(u32, bool)]) -> Vec<Pot> {
+    let mut levels: Vec<u32> = contributions
+        .iter()
+        .filter(|&&(amount, folded)| !folded && amount > 0)
+        .map(|&(amount, _)| amount)
+        .collect();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let mut pots: Vec<Pot> = Vec::with_capacity(levels.len());
+    let mut previous = 0;
+    for &level in &levels {
+        let amount = contributions
+            .iter()
+            .map(|&(contributed, _)| contributed.min(level) - contributed.min(previous))
+            .sum();
+        let eligible = contributions
+            .iter()
+            .enumerate()
+            .filter(|(_, &(contributed, folded))| !folded && contributed >= level)
+            .map(|(i, _)| i)
+            .collect();
+        pots.push(Pot { amount, eligible });
+        previous = level;
+    }
+
+    // 弃牌玩家投入超过所有未弃牌玩家的部分，归入最后一个底池
+    let remainder: u32 = contributions
+        .iter()
+        .map(|&(contributed, _)| contributed.saturating_sub(previous))
+        .sum();
+    if remainder > 0 {
+        if let Some(last) = pots.last_mut() {
+            last.amount += remainder;
+        }
+    }
+    pots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_pot() {
+        assert_eq!(
+            split_pot(0, 300, &[2, 0]),
+            vec![
+                Payout {
+                    pot: 0,
+                    run: 0,
+                    player: 2,
+                    amount: 150
+                },
+                Payout {
+                    pot: 0,
+                    run: 0,
+                    player: 0,
+                    amount: 150
+                },
+            ]
+        );
+        let payouts = split_pot(1, 101, &[3, 1, 2]);
+        assert_eq!(
+            payouts.iter().map(|p| p.amount).collect::<Vec<u32>>(),
+            vec![34, 34, 33]
+        );
+    }
+
+    #[test]
+    fn test_single_pot() {
+        let pots = build_pots(&[(100, false), (100, false), (100, false)]);
+        assert_eq!(
+            pots,
+            vec![Pot {
+                amount: 300,
+                eligible: vec![0, 1, 2]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_side_pots() {
+        // 玩家0 全下50，玩家1 全下200，玩家2 和 玩家3 各投入500
+        let pots = build_pots(&[(50, false), (200, false), (500, false), (500, false)]);
+        assert_eq!(
+            pots,
+            vec![
+                Pot {
+                    amount: 200,
+                    eligible: vec![0, 1, 2, 3]
+                },
+                Pot {
+                    amount: 450,
+                    eligible: vec![1, 2, 3]
+                },
+                Pot {
+                    amount: 600,
+                    eligible: vec![2, 3]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_folded_contributions() {
+        // 玩家1 投入150后弃牌，筹码分别计入主池和边池
+        let pots = build_pots(&[(100, false), (150, true), (300, false)]);
+        assert_eq!(
+            pots,
+            vec![
+                Pot {
+                    amount: 300,
+                    eligible: vec![0, 2]
+                },
+                Pot {
+                    amount: 250,
+                    eligible: vec![2]
+                },
+            ]
+        );
+        assert_eq!(pots.iter().map(|pot| pot.amount).sum::<u32>(), 550);
+    }
+
+    #[test]
+    fn test_folded_over_contribution() {
+        let pots = build_pots(&[(400, true), (100, false), (100, false)]);
+        assert_eq!(
+            pots,
+            vec![Pot {
+                amount: 600,
+                eligible: vec![1, 2]
+            }]
+        );
+    }
+}
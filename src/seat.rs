@@ -0,0 +1,45 @@
+use std::fmt;
+
+/// 牌桌的座位数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableSize {
+    HeadsUp,
+    SixMax,
+    NineMax,
+    TenMax,
+}
+
+impl TableSize {
+    pub fn seats(self) -> u32 {
+        match self {
+            TableSize::HeadsUp => 2,
+            TableSize::SixMax => 6,
+            TableSize::NineMax => 9,
+            TableSize::TenMax => 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SeatError {
+    // 只能在两手牌之间入座或离座
+    HandInProgress,
+    NoSuchSeat(u32),
+    SeatTaken(u32),
+    EmptySeat(u32),
+    TableFull,
+}
+
+impl fmt::Display for SeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeatError::HandInProgress => write!(f, "wait for the current hand to finish"),
+            SeatError::NoSuchSeat(seat) => write!(f, "there is no seat {}", seat),
+            SeatError::SeatTaken(seat) => write!(f, "seat {} is taken", seat),
+            SeatError::EmptySeat(seat) => write!(f, "seat {} is empty", seat),
+            SeatError::TableFull => write!(f, "the table is full"),
+        }
+    }
+}
+
+impl std::error::Error for SeatError {}
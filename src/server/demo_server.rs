@@ -1,18 +1,190 @@
-mod command;
-use crate::command::FromClientMessage;
 use message_io::network::{Endpoint, NetEvent, Transport};
-use message_io::node::{self};
+use message_io::node::{self, NodeEvent, NodeHandler};
+use pocker::action::{Action, PreAction};
+use pocker::clock::ActionClock;
+use pocker::command::{FromClientMessage, FromServerMessage};
+use pocker::game::{Game, HandPhase};
+use pocker::history::HandHistory;
+use pocker::player::PlayerInfo;
+use pocker::seat::TableSize;
 
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const TABLE_SIZE: TableSize = TableSize::HeadsUp;
+const INITIAL_CHIPS: u32 = 1000;
+const SECONDS_PER_ACTION: u64 = 30;
+const TIME_BANK_SECONDS: u64 = 60;
+const TIME_BANK_REFILL_SECONDS: u64 = 5;
 
 struct ClientInfo {
-    count: usize,
+    // 客户端对应玩家的 id，座位和下标会随玩家入座离座变化
+    id: u64,
+}
+
+enum Signal {
+    // 第 n 次等待玩家行动的计时到了
+    Timeout(u64),
+}
+
+/// 正在等待的行动，每次轮到玩家行动时编号加一，过期的计时信号不再生效
+struct Turn {
+    id: u64,
+    started: Instant,
+}
+
+fn send(handler: &NodeHandler<Signal>, endpoint: Endpoint, message: &FromServerMessage) {
+    let output_data = bincode::serialize(message).unwrap();
+    handler.network().send(endpoint, &output_data);
+}
+
+fn broadcast(
+    handler: &NodeHandler<Signal>,
+    clients: &HashMap<Endpoint, ClientInfo>,
+    message: &FromServerMessage,
+) {
+    for &endpoint in clients.keys() {
+        send(handler, endpoint, message);
+    }
+}
+
+/// 玩家 id 对应的下标
+fn player_index(game: &Game, id: u64) -> Option<usize> {
+    game.players.iter().position(|player| player.info.id == id)
+}
+
+/// 把入座玩家的资料按座位顺序发给所有客户端
+fn broadcast_players(
+    handler: &NodeHandler<Signal>,
+    clients: &HashMap<Endpoint, ClientInfo>,
+    game: &Game,
+) {
+    let players = game
+        .players
+        .iter()
+        .map(|player| player.info.clone())
+        .collect();
+    broadcast(handler, clients, &FromServerMessage::Players(players));
+}
+
+/// 把牌桌发给这位客户端，轮到他行动时一起发可以做的行动
+fn send_table(handler: &NodeHandler<Signal>, endpoint: Endpoint, game: &Game, index: usize) {
+    send(
+        handler,
+        endpoint,
+        &FromServerMessage::Table(game.view(index)),
+    );
+    if let (Some(to_act), Some(legal)) = (game.to_act(), game.legal_actions()) {
+        if to_act == index {
+            send(handler, endpoint, &FromServerMessage::YourTurn(legal));
+        }
+    }
+}
+
+/// 两手牌之间让等待的新玩家带初始筹码入座
+fn seat_waiting(
+    handler: &NodeHandler<Signal>,
+    clients: &mut HashMap<Endpoint, ClientInfo>,
+    waiting: &mut Vec<(Endpoint, PlayerInfo)>,
+    game: &mut Game,
+) {
+    for (endpoint, info) in waiting.drain(..) {
+        let id = info.id;
+        match game.join(None, info, INITIAL_CHIPS) {
+            Ok(index) => {
+                clients.insert(endpoint, ClientInfo { id });
+                let info = game.players[index].info.clone();
+                send(handler, endpoint, &FromServerMessage::Welcome(info));
+            }
+            Err(e) => send(handler, endpoint, &FromServerMessage::Error(e.to_string())),
+        }
+    }
+    broadcast_players(handler, clients, game);
+}
+
+/*
+ * 推进牌局直到需要某位玩家行动
+ * 本轮下注结束时 advance，一手牌结束后让等待的玩家入座，然后开始下一手
+ * 轮到玩家行动时开始计时
+ */
+fn drive(
+    handler: &NodeHandler<Signal>,
+    clients: &mut HashMap<Endpoint, ClientInfo>,
+    waiting: &mut Vec<(Endpoint, PlayerInfo)>,
+    game: &mut Game,
+    turn: &mut Turn,
+) {
+    loop {
+        if let Some((seat, action)) = game.apply_pre_action() {
+            let message = format!("{} {} (auto)", game.players[seat].info, action);
+            broadcast(handler, clients, &FromServerMessage::Info(message));
+            continue;
+        }
+        if let Some(seat) = game.to_act() {
+            turn.id += 1;
+            turn.started = Instant::now();
+            if let Some(clock) = &game.clock {
+                let allowance = clock.allowance(game.players[seat].info.id);
+                handler
+                    .signals()
+                    .send_with_timer(Signal::Timeout(turn.id), allowance);
+            }
+            for (&endpoint, client) in clients.iter() {
+                if let Some(index) = player_index(game, client.id) {
+                    send_table(handler, endpoint, game, index);
+                }
+            }
+            return;
+        }
+        if game.phase != HandPhase::Complete {
+            // 摊牌前把公共牌和亮出的手牌发给所有人
+            if game.advance().unwrap() == HandPhase::Showdown {
+                for (&endpoint, client) in clients.iter() {
+                    if let Some(index) = player_index(game, client.id) {
+                        send_table(handler, endpoint, game, index);
+                    }
+                }
+            }
+            continue;
+        }
+        // 种子只记在服务器的输出里，客户端拿到种子就能还原所有人的手牌
+        print!("{}", game.history);
+        let history = HandHistory {
+            seed: None,
+            ..game.history.clone()
+        };
+        broadcast(handler, clients, &FromServerMessage::HandComplete(history));
+        game.move_button();
+        if !waiting.is_empty() {
+            seat_waiting(handler, clients, waiting, game);
+        }
+        if game.active_players() < 2 {
+            let message = "Waiting for players".to_string();
+            broadcast(handler, clients, &FromServerMessage::Info(message));
+            return;
+        }
+        game.start_hand();
+    }
 }
 
 fn main() {
-    let (handler, listener) = node::split::<()>();
+    let (handler, listener) = node::split::<Signal>();
 
     let mut clients: HashMap<Endpoint, ClientInfo> = HashMap::new();
+    // 牌局进行中连接的新玩家，这手牌结束后入座
+    let mut waiting: Vec<(Endpoint, PlayerInfo)> = Vec::new();
+    let mut game = Game::with_table_size(TABLE_SIZE);
+    game.clock = Some(ActionClock::new(
+        Duration::from_secs(SECONDS_PER_ACTION),
+        Duration::from_secs(TIME_BANK_SECONDS),
+        Duration::from_secs(TIME_BANK_REFILL_SECONDS),
+    ));
+    let mut turn = Turn {
+        id: 0,
+        started: Instant::now(),
+    };
+    // 没有带 id 的新玩家分配的下一个 id
+    let mut next_id: u64 = 0;
 
     match handler
         .network()
@@ -23,28 +195,162 @@ fn main() {
     }
 
     // Read incoming network events.
-    listener.for_each(move |event| match event.network() {
-        NetEvent::Connected(_, _) => unreachable!(), // Used for explicit connections.
-        NetEvent::Accepted(endpoint, _listener) => {
-            clients.insert(endpoint, ClientInfo { count: 0 });
-            println!(
-                "Client ({}) connected (total clients: {})",
-                endpoint.addr(),
-                clients.len()
-            );
-        }
-        NetEvent::Message(endpoint, input_data) => {
-            let message: FromClientMessage = bincode::deserialize(&input_data).unwrap();
-            println!("Received: {:?}", message);
-            handler.network().send(endpoint, input_data);
-        }
-        NetEvent::Disconnected(endpoint) => {
-            clients.remove(&endpoint).unwrap();
-            println!(
-                "Client ({}) disconnected (total clients: {})",
-                endpoint.addr(),
-                clients.len()
-            );
+    listener.for_each(move |event| match event {
+        NodeEvent::Signal(Signal::Timeout(id)) => {
+            let Some(seat) = game.to_act().filter(|_| id == turn.id) else {
+                return;
+            };
+            let player = game.players[seat].info.clone();
+            if let Some(clock) = &mut game.clock {
+                clock.charge(player.id, clock.allowance(player.id));
+            }
+            let action = Game::timeout_action(&game.legal_actions().unwrap());
+            game.apply_action(seat, action).unwrap();
+            let message = format!("{} ran out of time and {}s", player, action);
+            broadcast(&handler, &clients, &FromServerMessage::Info(message));
+            drive(&handler, &mut clients, &mut waiting, &mut game, &mut turn);
         }
+        NodeEvent::Network(net_event) => match net_event {
+            NetEvent::Connected(_, _) => unreachable!(), // Used for explicit connections.
+            NetEvent::Accepted(endpoint, _listener) => {
+                // 等客户端发来 Hello 再决定入座还是回到原来的座位
+                println!("Client ({}) connected", endpoint.addr());
+            }
+            NetEvent::Message(endpoint, input_data) => {
+                let message: FromClientMessage = match bincode::deserialize(input_data) {
+                    Ok(message) => message,
+                    Err(_) => return println!("Invalid message from {}", endpoint.addr()),
+                };
+                println!("Received: {:?}", message);
+                if let FromClientMessage::Hello(id) = message {
+                    if clients.contains_key(&endpoint)
+                        || waiting.iter().any(|(waiting, _)| *waiting == endpoint)
+                    {
+                        let message = FromServerMessage::Error("already seated".to_string());
+                        return send(&handler, endpoint, &message);
+                    }
+                    if let Some(index) = id.and_then(|id| player_index(&game, id)) {
+                        // 断线重连: 回到原来的座位，牌局中的手牌和行动都保留
+                        let info = game.players[index].info.clone();
+                        if clients.values().any(|client| client.id == info.id) {
+                            let message =
+                                FromServerMessage::Error(format!("{} is already connected", info));
+                            return send(&handler, endpoint, &message);
+                        }
+                        clients.insert(endpoint, ClientInfo { id: info.id });
+                        game.players[index].sitting_out = false;
+                        send(&handler, endpoint, &FromServerMessage::Welcome(info));
+                        broadcast_players(&handler, &clients, &game);
+                        if game.phase != HandPhase::Complete {
+                            return send_table(&handler, endpoint, &game, index);
+                        }
+                    } else {
+                        let id = id.unwrap_or(next_id);
+                        if waiting.iter().any(|(_, info)| info.id == id) {
+                            let message = FromServerMessage::Error(format!("id {} is taken", id));
+                            return send(&handler, endpoint, &message);
+                        }
+                        next_id = next_id.max(id + 1);
+                        let info = PlayerInfo::new(id, &format!("Player {}", id));
+                        waiting.push((endpoint, info));
+                        if game.phase != HandPhase::Complete {
+                            let message = "You will be seated after this hand".to_string();
+                            return send(&handler, endpoint, &FromServerMessage::Info(message));
+                        }
+                        seat_waiting(&handler, &mut clients, &mut waiting, &mut game);
+                    }
+                    if game.phase == HandPhase::Complete && game.active_players() >= 2 {
+                        game.start_hand();
+                        drive(&handler, &mut clients, &mut waiting, &mut game, &mut turn);
+                    }
+                    return;
+                }
+                let Some(seat) = clients
+                    .get(&endpoint)
+                    .and_then(|client| player_index(&game, client.id))
+                else {
+                    let message = FromServerMessage::Error("say hello first".to_string());
+                    return send(&handler, endpoint, &message);
+                };
+                match message {
+                    FromClientMessage::Hello(_) => unreachable!(),
+                    FromClientMessage::Chat(text) => {
+                        let message = format!("{}: {}", game.players[seat].info, text);
+                        broadcast(&handler, &clients, &FromServerMessage::Info(message));
+                    }
+                    FromClientMessage::Profile {
+                        name,
+                        avatar,
+                        country,
+                    } => {
+                        let info = &mut game.players[seat].info;
+                        info.name = name;
+                        info.avatar = avatar;
+                        info.country = country;
+                        broadcast_players(&handler, &clients, &game);
+                    }
+                    // "auto call" 跟注到当前的最高下注，"auto off" 取消
+                    FromClientMessage::Command(command) if command.starts_with("auto ") => {
+                        let result = match &command["auto ".len()..] {
+                            "off" => Ok(None),
+                            "call" => Ok(Some(PreAction::Call(game.current_bet))),
+                            text => text
+                                .parse::<PreAction>()
+                                .map(Some)
+                                .map_err(|e| e.to_string()),
+                        }
+                        .and_then(|pre_action| {
+                            game.set_pre_action(seat, pre_action)
+                                .map(|_| pre_action)
+                                .map_err(|e| e.to_string())
+                        });
+                        let message = match result {
+                            Ok(Some(pre_action)) => {
+                                FromServerMessage::Info(format!("Auto {}", pre_action))
+                            }
+                            Ok(None) => FromServerMessage::Info("Auto off".to_string()),
+                            Err(e) => FromServerMessage::Error(e),
+                        };
+                        send(&handler, endpoint, &message);
+                    }
+                    FromClientMessage::Command(command) => {
+                        let result = command
+                            .parse::<Action>()
+                            .map_err(|e| e.to_string())
+                            .and_then(|action| {
+                                game.apply_action(seat, action)
+                                    .map(|_| action)
+                                    .map_err(|e| e.to_string())
+                            });
+                        match result {
+                            Ok(action) => {
+                                let id = game.players[seat].info.id;
+                                if let Some(clock) = &mut game.clock {
+                                    clock.charge(id, turn.started.elapsed());
+                                }
+                                let message = format!("{} {}", game.players[seat].info, action);
+                                broadcast(&handler, &clients, &FromServerMessage::Info(message));
+                                drive(&handler, &mut clients, &mut waiting, &mut game, &mut turn);
+                            }
+                            Err(e) => send(&handler, endpoint, &FromServerMessage::Error(e)),
+                        }
+                    }
+                }
+            }
+            NetEvent::Disconnected(endpoint) => {
+                // 掉线的玩家暂离并保留座位，当前这手牌由计时器替他过牌或弃牌
+                waiting.retain(|(waiting, _)| *waiting != endpoint);
+                if let Some(client) = clients.remove(&endpoint) {
+                    if let Some(index) = player_index(&game, client.id) {
+                        game.players[index].sitting_out = true;
+                    }
+                }
+                println!(
+                    "Client ({}) disconnected (total clients: {})",
+                    endpoint.addr(),
+                    clients.len()
+                );
+            }
+        },
     });
 }
@@ -0,0 +1,583 @@
+use crate::controller::PlayerController;
+use crate::game::Game;
+use crate::player::{Player, PlayerInfo};
+use crate::pot::Payout;
+use crate::seat::SeatError;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PurchaseKind {
+    // 入场买入
+    BuyIn,
+    // 输光后重购
+    Rebuy,
+    // 重购期结束时的加购
+    AddOn,
+    // 现金局补码到最大买入
+    TopUp,
+}
+
+impl fmt::Display for PurchaseKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            PurchaseKind::BuyIn => "buy-in",
+            PurchaseKind::Rebuy => "rebuy",
+            PurchaseKind::AddOn => "add-on",
+            PurchaseKind::TopUp => "top-up",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// 询问玩家是否购买筹码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PurchaseOffer {
+    pub kind: PurchaseKind,
+    pub amount: u32,
+}
+
+/// 账本中的一笔购买记录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LedgerEntry {
+    // 在第几手牌之后购买，0 为入场
+    pub hand: u32,
+    pub player: usize,
+    pub kind: PurchaseKind,
+    pub amount: u32,
+}
+
+/// 重购期，按手数或盲注级别计算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebuyPeriod {
+    Hands(u32),
+    Levels(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuyRules {
+    pub amount: u32,
+    pub max_rebuys: u32,
+    pub period: RebuyPeriod,
+    // 重购期结束时每位玩家可以加购一次
+    pub add_on: Option<u32>,
+}
+
+/// 牌局结束时一位玩家的名次
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    pub player: usize,
+    pub name: String,
+    pub chips: u32,
+    // 输光筹码的那一手，None 表示还在场上
+    pub busted_hand: Option<u32>,
+    // 输光那一手开始时的筹码，同一手出局时筹码多的名次靠前
+    pub busted_stack: u32,
+}
+
+/// 连续打多手牌，直到只剩一位玩家有筹码，或达到手数/时间上限
+pub struct Session {
+    pub game: Game,
+    controllers: Vec<Box<dyn PlayerController>>,
+    pub hands_played: u32,
+    pub max_hands: Option<u32>,
+    pub time_limit: Option<Duration>,
+    pub rebuy: Option<RebuyRules>,
+    // 现金局最大买入，低于它的玩家每手牌之间可以补码
+    pub max_buy_in: Option<u32>,
+    // 当前盲注级别，用于按级别计算重购期
+    pub level: u32,
+    pub ledger: Vec<LedgerEntry>,
+    busted_hand: Vec<Option<u32>>,
+    busted_stack: Vec<u32>,
+    rebuys: Vec<u32>,
+    add_on_offered: bool,
+    started: Instant,
+}
+
+impl Session {
+    pub fn new(game: Game, controllers: Vec<Box<dyn PlayerController>>) -> Self {
+        assert_eq!(
+            game.players.len(),
+            controllers.len(),
+            "every player needs a controller"
+        );
+        let ledger = game
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, player)| LedgerEntry {
+                hand: 0,
+                player: i,
+                kind: PurchaseKind::BuyIn,
+                amount: player.chips,
+            })
+            .collect();
+        let player_count = game.players.len();
+        Self {
+            game,
+            controllers,
+            hands_played: 0,
+            max_hands: None,
+            time_limit: None,
+            rebuy: None,
+            max_buy_in: None,
+            level: 0,
+            ledger,
+            busted_hand: vec![None; player_count],
+            busted_stack: vec![0; player_count],
+            rebuys: vec![0; player_count],
+            add_on_offered: false,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game.active_players() < 2
+            || self.max_hands.is_some_and(|max| self.hands_played >= max)
+            || self
+                .time_limit
+                .is_some_and(|limit| self.started.elapsed() >= limit)
+    }
+
+    pub fn in_rebuy_period(&self) -> bool {
+        match self.rebuy.map(|rules| rules.period) {
+            Some(RebuyPeriod::Hands(hands)) => self.hands_played < hands,
+            Some(RebuyPeriod::Levels(levels)) => self.level < levels,
+            None => false,
+        }
+    }
+
+    /*
+     * 两手牌之间新玩家带 chips 筹码入座，记一笔入场买入
+     * 控制器和出局记录插在 `Game::join` 返回的下标上，账本里后面玩家的下标跟着后移
+     * @return usize 玩家的下标
+     */
+    pub fn join(
+        &mut self,
+        seat: Option<u32>,
+        info: PlayerInfo,
+        chips: u32,
+        controller: Box<dyn PlayerController>,
+    ) -> Result<usize, SeatError> {
+        let index = self.game.join(seat, info, chips)?;
+        self.controllers.insert(index, controller);
+        self.busted_hand.insert(index, None);
+        self.busted_stack.insert(index, 0);
+        self.rebuys.insert(index, 0);
+        for entry in &mut self.ledger {
+            if entry.player >= index {
+                entry.player += 1;
+            }
+        }
+        self.ledger.push(LedgerEntry {
+            hand: self.hands_played,
+            player: index,
+            kind: PurchaseKind::BuyIn,
+            amount: chips,
+        });
+        Ok(index)
+    }
+
+    /// `seat` 号座位的玩家离座，连同他的控制器、出局记录和账本记录一起移除
+    pub fn leave(&mut self, seat: u32) -> Result<Player, SeatError> {
+        let index = self
+            .game
+            .player_at(seat)
+            .ok_or(SeatError::EmptySeat(seat))?;
+        let player = self.game.leave(seat)?;
+        self.controllers.remove(index);
+        self.busted_hand.remove(index);
+        self.busted_stack.remove(index);
+        self.rebuys.remove(index);
+        self.ledger.retain(|entry| entry.player != index);
+        for entry in &mut self.ledger {
+            if entry.player > index {
+                entry.player -= 1;
+            }
+        }
+        Ok(player)
+    }
+
+    /// 询问玩家是否购买，接受则加筹码并记账
+    fn offer(&mut self, player: usize, kind: PurchaseKind, amount: u32) -> bool {
+        let offer = PurchaseOffer { kind, amount };
+        if !self.controllers[player].accept_purchase(&offer) {
+            return false;
+        }
+        println!(
+            "{} {} {} chips",
+            self.game.players[player].info, kind, amount
+        );
+        self.game.players[player].chips += amount;
+        self.ledger.push(LedgerEntry {
+            hand: self.hands_played,
+            player,
+            kind,
+            amount,
+        });
+        true
+    }
+
+    /*
+     * 两手牌之间处理购买筹码
+     * 重购期内刚输光的玩家可以重购，拒绝重购即出局，之后不再询问
+     * 重购期结束时还在场的玩家可以加购一次，
+     * 现金局还有筹码但低于最大买入的玩家可以补码
+     */
+    fn offer_purchases(&mut self) {
+        if let Some(rules) = self.rebuy {
+            if self.in_rebuy_period() {
+                for i in 0..self.game.players.len() {
+                    if self.game.players[i].chips == 0
+                        && self.busted_hand[i].is_none()
+                        && self.rebuys[i] < rules.max_rebuys
+                        && self.offer(i, PurchaseKind::Rebuy, rules.amount)
+                    {
+                        self.rebuys[i] += 1;
+                    }
+                }
+            } else if let (Some(add_on), false) = (rules.add_on, self.add_on_offered) {
+                self.add_on_offered = true;
+                for i in 0..self.game.players.len() {
+                    if self.game.players[i].chips > 0 {
+                        self.offer(i, PurchaseKind::AddOn, add_on);
+                    }
+                }
+            }
+        }
+        if let Some(max_buy_in) = self.max_buy_in {
+            for i in 0..self.game.players.len() {
+                // 已出局或输光的玩家不补码，输光要走重购
+                let chips = self.game.players[i].chips;
+                if self.busted_hand[i].is_none() && chips > 0 && chips < max_buy_in {
+                    self.offer(i, PurchaseKind::TopUp, max_buy_in - chips);
+                }
+            }
+        }
+    }
+
+    /// 打一手牌，之后处理购买筹码并记录输光筹码的玩家
+    pub fn play_hand(&mut self) -> Vec<Payout> {
+        let stacks: Vec<u32> = self.game.players.iter().map(|p| p.chips).collect();
+        let payouts = self.game.play_round(&mut self.controllers);
+        self.hands_played += 1;
+        self.offer_purchases();
+        for (i, player) in self.game.players.iter().enumerate() {
+            if player.chips == 0 && self.busted_hand[i].is_none() {
+                println!("{} is eliminated", player.info);
+                self.busted_hand[i] = Some(self.hands_played);
+                self.busted_stack[i] = stacks[i];
+            }
+        }
+        payouts
+    }
+
+    pub fn run(&mut self) -> Vec<Standing> {
+        while !self.is_over() {
+            self.play_hand();
+        }
+        let standings = self.standings();
+        print_standings(&standings);
+        standings
+    }
+
+    /*
+     * 按筹码从多到少排名，已出局的玩家越晚出局名次越靠前
+     * 同一手出局的玩家，这手开始时筹码多的名次靠前
+     */
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .game
+            .players
+            .iter()
+            .enumerate()
+            .map(|(i, player)| Standing {
+                player: i,
+                name: player.info.name.clone(),
+                chips: player.chips,
+                busted_hand: self.busted_hand[i],
+                busted_stack: self.busted_stack[i],
+            })
+            .collect();
+        standings.sort_by_key(|standing| {
+            (
+                std::cmp::Reverse(standing.chips),
+                std::cmp::Reverse(standing.busted_hand.unwrap_or(u32::MAX)),
+                std::cmp::Reverse(standing.busted_stack),
+            )
+        });
+        standings
+    }
+}
+
+pub fn print_standings(standings: &[Standing]) {
+    println!("Final standings:");
+    for (place, standing) in standings.iter().enumerate() {
+        match standing.busted_hand {
+            Some(hand) => println!("{}. {} busted in hand {}", place + 1, standing.name, hand),
+            None => println!(
+                "{}. {} with {} chips",
+                place + 1,
+                standing.name,
+                standing.chips
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::{Action, LegalActions};
+    use crate::card::{parse_cards, Card, Deck};
+    use crate::controller::{
+        boxed_controllers, PassiveBot, RandomBot, ScriptedController, TableView,
+    };
+    use crate::seat::TableSize;
+
+    // 总是接受购买的机器人
+    struct Buyer;
+
+    impl PlayerController for Buyer {
+        fn decide(&mut self, view: &TableView, legal: &LegalActions) -> Action {
+            PassiveBot.decide(view, legal)
+        }
+
+        fn accept_purchase(&mut self, _offer: &PurchaseOffer) -> bool {
+            true
+        }
+    }
+
+    fn buyers(count: usize) -> Vec<Box<dyn PlayerController>> {
+        boxed_controllers(count, |_| Buyer)
+    }
+
+    #[test]
+    fn test_rebuy() {
+        let mut session = Session::new(Game::new(3, 1000), buyers(3));
+        session.rebuy = Some(RebuyRules {
+            amount: 500,
+            max_rebuys: 1,
+            period: RebuyPeriod::Hands(2),
+            add_on: Some(300),
+        });
+        session.game.players[1].chips = 0;
+        session.offer_purchases();
+        assert_eq!(session.game.players[1].chips, 500);
+        // 重购次数用完
+        session.game.players[1].chips = 0;
+        session.offer_purchases();
+        assert_eq!(session.game.players[1].chips, 0);
+        assert_eq!(
+            session.ledger.last(),
+            Some(&LedgerEntry {
+                hand: 0,
+                player: 1,
+                kind: PurchaseKind::Rebuy,
+                amount: 500
+            })
+        );
+        assert_eq!(session.ledger.len(), 4);
+    }
+
+    #[test]
+    fn test_add_on_after_rebuy_period() {
+        let mut session = Session::new(Game::new(2, 1000), buyers(2));
+        session.rebuy = Some(RebuyRules {
+            amount: 500,
+            max_rebuys: 3,
+            period: RebuyPeriod::Levels(1),
+            add_on: Some(300),
+        });
+        session.level = 1;
+        session.game.players[1].chips = 0;
+        session.offer_purchases();
+        // 重购期已过，只有还在场的玩家可以加购，且只有一次
+        assert_eq!(session.game.players[0].chips, 1300);
+        assert_eq!(session.game.players[1].chips, 0);
+        session.offer_purchases();
+        assert_eq!(session.game.players[0].chips, 1300);
+    }
+
+    #[test]
+    fn test_top_up() {
+        let mut session = Session::new(Game::new(2, 1000), buyers(2));
+        session.max_buy_in = Some(2000);
+        session.game.players[0].chips = 400;
+        session.offer_purchases();
+        assert_eq!(session.game.players[0].chips, 2000);
+        assert_eq!(session.game.players[1].chips, 2000);
+        let top_ups: u32 = session
+            .ledger
+            .iter()
+            .filter(|entry| entry.kind == PurchaseKind::TopUp)
+            .map(|entry| entry.amount)
+            .sum();
+        assert_eq!(top_ups, 2600);
+    }
+
+    #[test]
+    fn test_no_top_up_when_busted() {
+        let mut session = Session::new(Game::new(3, 1000), buyers(3));
+        session.rebuy = Some(RebuyRules {
+            amount: 500,
+            max_rebuys: 0,
+            period: RebuyPeriod::Hands(10),
+            add_on: None,
+        });
+        session.max_buy_in = Some(1000);
+        session.game.players[1].chips = 0;
+        session.play_hand();
+        assert_eq!(session.busted_hand[1], Some(1));
+        session.play_hand();
+        // 输光的玩家不能靠补码回到牌局
+        assert_eq!(session.game.players[1].chips, 0);
+        assert!(!session
+            .ledger
+            .iter()
+            .any(|entry| entry.player == 1 && entry.kind == PurchaseKind::TopUp));
+    }
+
+    #[test]
+    fn test_declined_rebuy_is_eliminated() {
+        let controllers = boxed_controllers(2, |_| PassiveBot);
+        let mut session = Session::new(Game::new(2, 1000), controllers);
+        session.rebuy = Some(RebuyRules {
+            amount: 500,
+            max_rebuys: 1,
+            period: RebuyPeriod::Hands(10),
+            add_on: None,
+        });
+        session.game.players[1].chips = 0;
+        session.offer_purchases();
+        assert_eq!(session.game.players[1].chips, 0);
+        assert!(session.is_over());
+    }
+
+    // 第一次拒绝购买，之后都接受
+    struct Hesitant {
+        asked: bool,
+    }
+
+    impl PlayerController for Hesitant {
+        fn decide(&mut self, view: &TableView, legal: &LegalActions) -> Action {
+            PassiveBot.decide(view, legal)
+        }
+
+        fn accept_purchase(&mut self, _offer: &PurchaseOffer) -> bool {
+            std::mem::replace(&mut self.asked, true)
+        }
+    }
+
+    #[test]
+    fn test_no_rebuy_after_elimination() {
+        let controllers: Vec<Box<dyn PlayerController>> = vec![
+            Box::new(PassiveBot),
+            Box::new(Hesitant { asked: false }),
+            Box::new(PassiveBot),
+        ];
+        let mut session = Session::new(Game::new(3, 1000), controllers);
+        session.rebuy = Some(RebuyRules {
+            amount: 500,
+            max_rebuys: 2,
+            period: RebuyPeriod::Hands(10),
+            add_on: None,
+        });
+        session.game.players[1].chips = 0;
+        session.play_hand();
+        assert_eq!(session.busted_hand[1], Some(1));
+        // 出局后不再询问重购
+        session.play_hand();
+        assert_eq!(session.game.players[1].chips, 0);
+        assert_eq!(session.busted_hand[1], Some(1));
+        assert!(!session
+            .ledger
+            .iter()
+            .any(|entry| entry.kind == PurchaseKind::Rebuy));
+    }
+
+    #[test]
+    fn test_same_hand_busts_ranked_by_stack() {
+        let mut game = Game::new(3, 1000);
+        game.players[1].chips = 200;
+        game.players[2].chips = 300;
+        game.deck = Deck::with_hands(
+            &[
+                Some((Card::from("As"), Card::from("Ah"))),
+                Some((Card::from("Ks"), Card::from("Kh"))),
+                Some((Card::from("Qs"), Card::from("Qh"))),
+            ],
+            &parse_cards("2c 3d 7h 8s Jd").unwrap(),
+        )
+        .unwrap();
+        let controllers = boxed_controllers(3, |_| ScriptedController::new(vec![Action::AllIn]));
+        let mut session = Session::new(game, controllers);
+        session.play_hand();
+        let standings = session.standings();
+        let order: Vec<(usize, Option<u32>)> = standings
+            .iter()
+            .map(|standing| (standing.player, standing.busted_hand))
+            .collect();
+        assert_eq!(order, vec![(0, None), (2, Some(1)), (1, Some(1))]);
+        assert_eq!(standings[1].busted_stack, 300);
+    }
+
+    #[test]
+    fn test_join_and_leave() {
+        let mut game = Game::with_table_size(TableSize::SixMax);
+        for (id, name) in ["Alice", "Bob", "Dave"].into_iter().enumerate() {
+            let info = PlayerInfo::new(id as u64, name);
+            game.join(Some(id as u32 * 2), info, 1000).unwrap();
+        }
+        let mut session = Session::new(game, boxed_controllers(3, |_| PassiveBot));
+        session.play_hand();
+        // Carol 坐在 Alice 和 Bob 之间，后面玩家的下标和账本都后移
+        let info = PlayerInfo::new(3, "Carol");
+        let index = session
+            .join(Some(1), info, 500, Box::new(PassiveBot))
+            .unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(session.ledger.last().unwrap().player, 1);
+        for _ in 0..4 {
+            session.play_hand();
+        }
+        assert_eq!(session.game.total_chips(), 3500);
+        let bob = session.leave(2).unwrap();
+        assert_eq!(bob.info.name, "Bob");
+        assert!(session.ledger.iter().all(|entry| entry.player < 3));
+        for _ in 0..4 {
+            session.play_hand();
+        }
+        assert_eq!(session.game.total_chips(), 3500 - bob.chips);
+        assert_eq!(session.standings().len(), 3);
+    }
+
+    #[test]
+    fn test_hand_limit() {
+        let controllers = boxed_controllers(3, |_| PassiveBot);
+        let mut session = Session::new(Game::new(3, 1000), controllers);
+        session.max_hands = Some(5);
+        let standings = session.run();
+        assert_eq!(session.hands_played, 5);
+        assert_eq!(standings.len(), 3);
+        assert_eq!(session.game.total_chips(), 3000);
+    }
+
+    #[test]
+    fn test_play_until_one_player_left() {
+        let controllers = boxed_controllers(4, RandomBot::with_seed);
+        let mut session = Session::new(Game::new(4, 200), controllers);
+        session.max_hands = Some(10_000);
+        let standings = session.run();
+        assert_eq!(session.game.funded_players(), 1);
+        assert_eq!(standings[0].chips, 800);
+        assert_eq!(standings[0].busted_hand, None);
+        // 越晚出局名次越靠前
+        let busted: Vec<u32> = standings[1..]
+            .iter()
+            .map(|standing| standing.busted_hand.unwrap())
+            .collect();
+        assert!(busted.windows(2).all(|w| w[0] >= w[1]));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::betting::Ante;
+use crate::controller::PlayerController;
+use crate::game::Game;
+use crate::session::{PurchaseKind, Session, Standing};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// 盲注级别的持续时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelDuration {
+    Hands(u32),
+    Minutes(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlindLevel {
+    pub small_blind: u32,
+    pub big_blind: u32,
+    pub ante: Ante,
+    pub duration: LevelDuration,
+}
+
+impl BlindLevel {
+    /// ante 为每位玩家的前注，0 表示没有前注
+    pub fn new(small_blind: u32, big_blind: u32, ante: u32, duration: LevelDuration) -> Self {
+        Self {
+            small_blind,
+            big_blind,
+            ante: if ante > 0 {
+                Ante::PerPlayer(ante)
+            } else {
+                Ante::None
+            },
+            duration,
+        }
+    }
+
+    /// 大盲替全桌下前注的级别
+    pub fn with_big_blind_ante(
+        small_blind: u32,
+        big_blind: u32,
+        ante: u32,
+        duration: LevelDuration,
+    ) -> Self {
+        Self {
+            small_blind,
+            big_blind,
+            ante: Ante::BigBlind(ante),
+            duration,
+        }
+    }
+
+    /// 把这一级的盲注和前注设置到牌桌上
+    pub fn apply(&self, game: &mut Game) {
+        game.small_blind = self.small_blind;
+        game.big_blind = self.big_blind;
+        game.ante = self.ante;
+    }
+}
+
+/// 盲注结构表，按手数或时间自动升级，最后一级一直保持
+#[derive(Debug, Clone)]
+pub struct BlindSchedule {
+    pub levels: Vec<BlindLevel>,
+    level: usize,
+    started: Instant,
+    start_hand: u32,
+}
+
+impl BlindSchedule {
+    pub fn new(levels: Vec<BlindLevel>) -> Self {
+        assert!(
+            !levels.is_empty(),
+            "a blind schedule needs at least one level"
+        );
+        Self {
+            levels,
+            level: 0,
+            started: Instant::now(),
+            start_hand: 0,
+        }
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn current(&self) -> &BlindLevel {
+        &self.levels[self.level]
+    }
+
+    /*
+     * 当前级别到时间后升到下一级
+     * @param hands_played: u32 已经打完的手数
+     * @return bool 是否升级
+     */
+    pub fn update(&mut self, hands_played: u32) -> bool {
+        if self.level + 1 >= self.levels.len() {
+            return false;
+        }
+        let finished = match self.current().duration {
+            LevelDuration::Hands(hands) => hands_played - self.start_hand >= hands,
+            LevelDuration::Minutes(minutes) => {
+                self.started.elapsed() >= Duration::from_secs(u64::from(minutes) * 60)
+            }
+        };
+        if finished {
+            self.level += 1;
+            self.started = Instant::now();
+            self.start_hand = hands_played;
+        }
+        finished
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrizeError {
+    // 百分比合计超过 100，奖金会超过奖池
+    OverPrizePool(u32),
+}
+
+impl fmt::Display for PrizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrizeError::OverPrizePool(total) => {
+                write!(f, "prize percentages add up to {}%, more than 100%", total)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrizeError {}
+
+/// 奖金结构，按名次分配奖池的百分比，合计不超过 100
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrizeStructure {
+    percentages: Vec<u32>,
+}
+
+impl PrizeStructure {
+    pub fn new(percentages: Vec<u32>) -> Result<Self, PrizeError> {
+        let total: u32 = percentages.iter().sum();
+        if total > 100 {
+            return Err(PrizeError::OverPrizePool(total));
+        }
+        Ok(Self { percentages })
+    }
+
+    pub fn percentages(&self) -> &[u32] {
+        &self.percentages
+    }
+
+    /// 常见的单桌赛奖金结构
+    pub fn sit_and_go(entrants: usize) -> Self {
+        let percentages = match entrants {
+            0..=3 => vec![100],
+            4..=6 => vec![65, 35],
+            _ => vec![50, 30, 20],
+        };
+        Self { percentages }
+    }
+
+    /// 每个名次的奖金，除不尽的零头归第一名
+    pub fn payouts(&self, prize_pool: u32) -> Vec<u32> {
+        let total: u32 = self.percentages.iter().sum();
+        let mut payouts: Vec<u32> = self
+            .percentages
+            .iter()
+            .map(|percentage| (u64::from(prize_pool) * u64::from(*percentage) / 100) as u32)
+            .collect();
+        if total == 100 {
+            let remainder = prize_pool.saturating_sub(payouts.iter().sum());
+            if let Some(first) = payouts.first_mut() {
+                *first += remainder;
+            }
+        }
+        payouts
+    }
+}
+
+/// 一位玩家的最终名次和奖金
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finish {
+    pub place: usize,
+    pub player: usize,
+    pub prize: u32,
+}
+
+/// 单桌锦标赛，打到只剩一位玩家为止
+pub struct Tournament {
+    pub session: Session,
+    pub schedule: BlindSchedule,
+    pub prizes: PrizeStructure,
+    // 每次买入/重购支付的报名费
+    pub buy_in: u32,
+}
+
+impl Tournament {
+    pub fn new(
+        controllers: Vec<Box<dyn PlayerController>>,
+        starting_stack: u32,
+        levels: Vec<BlindLevel>,
+        buy_in: u32,
+    ) -> Self {
+        let schedule = BlindSchedule::new(levels);
+        let mut game = Game::new(controllers.len(), starting_stack);
+        schedule.current().apply(&mut game);
+        let prizes = PrizeStructure::sit_and_go(controllers.len());
+        Self {
+            session: Session::new(game, controllers),
+            schedule,
+            prizes,
+            buy_in,
+        }
+    }
+
+    /// 买入和重购按报名费计算，加购按加购的金额计算
+    pub fn prize_pool(&self) -> u32 {
+        self.session
+            .ledger
+            .iter()
+            .map(|entry| match entry.kind {
+                PurchaseKind::BuyIn | PurchaseKind::Rebuy => self.buy_in,
+                PurchaseKind::AddOn => entry.amount,
+                PurchaseKind::TopUp => 0,
+            })
+            .sum()
+    }
+
+    /// 升级盲注后打一手牌
+    pub fn play_hand(&mut self) {
+        if self.schedule.update(self.session.hands_played) {
+            let level = self.schedule.current();
+            level.apply(&mut self.session.game);
+            println!(
+                "Blinds up: {}/{} ante {}",
+                level.small_blind, level.big_blind, level.ante
+            );
+        }
+        self.session.level = self.schedule.level() as u32;
+        self.session.play_hand();
+    }
+
+    pub fn run(&mut self) -> Vec<Finish> {
+        while !self.session.is_over() {
+            self.play_hand();
+        }
+        let finishes = self.finishes();
+        for finish in &finishes {
+            println!(
+                "{}. {} wins {}",
+                finish.place, self.session.game.players[finish.player].info, finish.prize
+            );
+        }
+        finishes
+    }
+
+    /// 按名次排列，奖金圈内的玩家分得奖金
+    pub fn finishes(&self) -> Vec<Finish> {
+        let payouts = self.prizes.payouts(self.prize_pool());
+        self.session
+            .standings()
+            .iter()
+            .enumerate()
+            .map(|(i, &Standing { player, .. })| Finish {
+                place: i + 1,
+                player,
+                prize: payouts.get(i).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::{boxed_controllers, RandomBot};
+    use crate::session::LedgerEntry;
+
+    fn levels() -> Vec<BlindLevel> {
+        vec![
+            BlindLevel::new(10, 20, 0, LevelDuration::Hands(2)),
+            BlindLevel::new(20, 40, 5, LevelDuration::Hands(2)),
+            BlindLevel::new(50, 100, 10, LevelDuration::Minutes(10)),
+        ]
+    }
+
+    #[test]
+    fn test_blind_schedule() {
+        let mut schedule = BlindSchedule::new(levels());
+        assert!(!schedule.update(1));
+        assert!(schedule.update(2));
+        assert_eq!(schedule.current().big_blind, 40);
+        assert!(!schedule.update(3));
+        assert!(schedule.update(4));
+        // 最后一级按时间，不会马上升级，也没有更高的级别
+        assert!(!schedule.update(100));
+        assert_eq!(schedule.level(), 2);
+
+        let mut game = Game::new(2, 1000);
+        schedule.current().apply(&mut game);
+        assert_eq!(game.small_blind, 50);
+        assert_eq!(game.ante, Ante::PerPlayer(10));
+        BlindLevel::with_big_blind_ante(50, 100, 100, LevelDuration::Hands(1)).apply(&mut game);
+        assert_eq!(game.ante, Ante::BigBlind(100));
+    }
+
+    #[test]
+    fn test_payouts() {
+        let prizes = PrizeStructure::sit_and_go(9);
+        assert_eq!(prizes.payouts(1001), vec![501, 300, 200]);
+        assert_eq!(PrizeStructure::sit_and_go(2).payouts(200), vec![200]);
+        // 百分比合计不到 100 时按比例计算，不补零头
+        let prizes = PrizeStructure::new(vec![60, 30]).unwrap();
+        assert_eq!(prizes.payouts(1001), vec![600, 300]);
+        assert_eq!(
+            PrizeStructure::new(vec![60, 50]),
+            Err(PrizeError::OverPrizePool(110))
+        );
+    }
+
+    #[test]
+    fn test_prize_pool_counts_add_ons() {
+        let controllers = boxed_controllers(3, RandomBot::with_seed);
+        let mut tournament = Tournament::new(controllers, 500, levels(), 100);
+        tournament.session.ledger.push(LedgerEntry {
+            hand: 2,
+            player: 0,
+            kind: PurchaseKind::AddOn,
+            amount: 300,
+        });
+        tournament.session.ledger.push(LedgerEntry {
+            hand: 2,
+            player: 1,
+            kind: PurchaseKind::Rebuy,
+            amount: 500,
+        });
+        assert_eq!(tournament.prize_pool(), 3 * 100 + 300 + 100);
+    }
+
+    #[test]
+    fn test_tournament_runs_to_winner() {
+        let controllers = boxed_controllers(4, RandomBot::with_seed);
+        let mut tournament = Tournament::new(controllers, 500, levels(), 100);
+        let finishes = tournament.run();
+        assert_eq!(tournament.session.game.funded_players(), 1);
+        assert_eq!(finishes.len(), 4);
+        assert_eq!(
+            finishes
+                .iter()
+                .map(|finish| finish.prize)
+                .collect::<Vec<u32>>(),
+            vec![260, 140, 0, 0]
+        );
+        assert_eq!(tournament.session.game.total_chips(), 2000);
+        assert!(tournament.session.game.big_blind >= 40);
+    }
+}